@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+
+use reqwest::{Client, Method, RequestBuilder, Response};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use starduck::{AdditionOrder, Application, Directives, ReconfigureOrder, RestartOrder};
+use url::Url;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::events::{event_stream, EventStream};
+use crate::types::*;
+
+#[derive(Serialize)]
+struct ExportOptions {
+    secrets: bool,
+}
+
+#[derive(Serialize)]
+struct ImportOptions {
+    mode: ImportMode,
+    dry_run: bool,
+}
+
+/// Talks to one bran instance. Cloning is cheap, clones share the
+/// connection pool.
+#[derive(Debug, Clone)]
+pub struct BranClient {
+    base: Url,
+    http: Client,
+    token: Option<String>,
+}
+
+impl BranClient {
+    pub fn new(base: &str) -> Result<Self> {
+        Self::with_http(base, Client::new())
+    }
+
+    /// Uses `http` for the requests, for custom TLS roots or timeouts.
+    pub fn with_http(base: &str, http: Client) -> Result<Self> {
+        let base = Url::parse(base)?;
+
+        // Request paths are appended to it
+        if !matches!(base.scheme(), "http" | "https") || base.cannot_be_a_base() {
+            return Err(Error::NotHttp(base));
+        }
+
+        Ok(Self {
+            base,
+            http,
+            token: None,
+        })
+    }
+
+    /// Sends `token` as a bearer token on every request.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base
+    }
+
+    // Applications
+
+    pub async fn list_apps(&self) -> Result<Vec<AppSummary>> {
+        self.get(&["apps"]).await
+    }
+
+    /// The spec of `app`, as set through the objective API.
+    pub async fn get_app(&self, app: &str) -> Result<Application> {
+        self.get(&["apps", app]).await
+    }
+
+    /// The state `app` last reported.
+    pub async fn get_app_status(&self, app: &str) -> Result<Application> {
+        self.get(&["apps", app, "status"]).await
+    }
+
+    pub async fn register_app(&self, app: &str, spec: &Application) -> Result<()> {
+        self.send(Method::POST, &["apps", app], spec).await
+    }
+
+    pub async fn update_app(&self, app: &str, spec: &Application) -> Result<()> {
+        self.send(Method::PATCH, &["apps", app], spec).await
+    }
+
+    /// Reports the observed state of `app`, as the monitors do.
+    pub async fn report_state(&self, app: &str, state: &Application) -> Result<()> {
+        self.send(Method::PUT, &["apps", app], state).await
+    }
+
+    pub async fn list_versions(&self, app: &str) -> Result<Vec<VersionSummary>> {
+        self.get(&["apps", app, "versions"]).await
+    }
+
+    pub async fn get_version(&self, app: &str, version: u64) -> Result<AppVersion> {
+        self.get(&["apps", app, "versions", &version.to_string()])
+            .await
+    }
+
+    pub async fn diff_versions(&self, app: &str, from: u64, to: u64) -> Result<Vec<Change>> {
+        let request = self
+            .request(Method::GET, &["apps", app, "versions", "diff"])
+            .query(&[("from", from), ("to", to)]);
+
+        decode(self.execute(request).await?).await
+    }
+
+    pub async fn rollback(&self, app: &str, version: u64) -> Result<()> {
+        let request = self.request(
+            Method::POST,
+            &["apps", app, "rollback", &version.to_string()],
+        );
+
+        self.execute(request).await.map(|_| ())
+    }
+
+    // Directives
+
+    /// Directives of every location of `app`.
+    pub async fn get_directives(&self, app: &str) -> Result<HashMap<String, Directives>> {
+        self.get(&["directives", app]).await
+    }
+
+    pub async fn set_addition(
+        &self,
+        app: &str,
+        location: &str,
+        order: &AdditionOrder,
+    ) -> Result<()> {
+        self.send(
+            Method::POST,
+            &["directives", "addition", app, location],
+            order,
+        )
+        .await
+    }
+
+    pub async fn set_reconfig(
+        &self,
+        app: &str,
+        location: &str,
+        order: &ReconfigureOrder,
+    ) -> Result<()> {
+        self.send(
+            Method::POST,
+            &["directives", "reconfig", app, location],
+            order,
+        )
+        .await
+    }
+
+    pub async fn set_restart(&self, app: &str, location: &str, order: &RestartOrder) -> Result<()> {
+        self.send(
+            Method::POST,
+            &["directives", "restart", app, location],
+            order,
+        )
+        .await
+    }
+
+    /// The dothing target of `app`, its secrets redacted.
+    pub async fn get_target(&self, app: &str) -> Result<DothingTarget> {
+        self.get(&["directives", "target", app]).await
+    }
+
+    pub async fn set_target(&self, app: &str, target: &DothingTarget) -> Result<()> {
+        self.send(Method::POST, &["directives", "target", app], target)
+            .await
+    }
+
+    /// The webhook of `app`, its secret redacted.
+    pub async fn get_webhook(&self, app: &str) -> Result<Webhook> {
+        self.get(&["directives", "webhook", app]).await
+    }
+
+    pub async fn set_webhook(&self, app: &str, webhook: &Webhook) -> Result<()> {
+        self.send(Method::POST, &["directives", "webhook", app], webhook)
+            .await
+    }
+
+    // Planner
+
+    pub async fn get_allocations(&self, app: &str) -> Result<Vec<Allocation>> {
+        self.get(&["planner", "allocations", app]).await
+    }
+
+    /// Forgets a failed addition, so the planner may order it again.
+    pub async fn clear_failed_allocation(&self, app: &str, device_uuid: Uuid) -> Result<()> {
+        let request = self.request(
+            Method::DELETE,
+            &["planner", "allocations", app, &device_uuid.to_string()],
+        );
+
+        self.execute(request).await.map(|_| ())
+    }
+
+    /// The orders planned on the last cycle, before the limits and
+    /// approvals.
+    pub async fn get_planned_orders(&self) -> Result<Vec<PlannedOrder>> {
+        self.get(&["planner", "planned"]).await
+    }
+
+    pub async fn get_deferred_orders(&self) -> Result<Vec<DeferredOrder>> {
+        self.get(&["planner", "deferred"]).await
+    }
+
+    /// Lets the orders of `app` awaiting approval go out on the next cycle,
+    /// only those for `location` if given.
+    pub async fn approve_orders(
+        &self,
+        app: &str,
+        location: Option<&str>,
+    ) -> Result<ApprovalReport> {
+        let mut request = self.request(Method::POST, &["planner", "deferred", app, "approve"]);
+
+        if let Some(location) = location {
+            request = request.query(&[("location", location)]);
+        }
+
+        decode(self.execute(request).await?).await
+    }
+
+    /// Circuit breaker of every dothing target, by URL.
+    pub async fn get_circuits(&self) -> Result<HashMap<String, CircuitBreaker>> {
+        self.get(&["planner", "circuit"]).await
+    }
+
+    // Register
+
+    /// Every application with its directives. The secrets are redacted
+    /// unless `secrets` is set. Needs an admin token.
+    pub async fn export_register(&self, secrets: bool) -> Result<RegisterSnapshot> {
+        let request = self
+            .request(Method::GET, &["register", "export"])
+            .query(&ExportOptions { secrets });
+
+        decode(self.execute(request).await?).await
+    }
+
+    /// Loads `snapshot` into the register, or only reports the changes it
+    /// would make when `dry_run` is set. Needs an admin token.
+    pub async fn import_register(
+        &self,
+        snapshot: &RegisterSnapshot,
+        mode: ImportMode,
+        dry_run: bool,
+    ) -> Result<ImportReport> {
+        let request = self
+            .request(Method::POST, &["register", "import"])
+            .query(&ImportOptions { mode, dry_run })
+            .json(snapshot);
+
+        decode(self.execute(request).await?).await
+    }
+
+    // Audit and events
+
+    pub async fn get_audit_entries(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        let request = self.request(Method::GET, &["audit"]).query(query);
+
+        decode(self.execute(request).await?).await
+    }
+
+    /// Streams the events of `app`, or of everything when `None`, until
+    /// bran closes the connection.
+    pub async fn events(&self, app: Option<&str>) -> Result<EventStream> {
+        let mut request = self.request(Method::GET, &["events"]);
+
+        if let Some(app) = app {
+            request = request.query(&[("app", app)]);
+        }
+
+        Ok(event_stream(self.execute(request).await?))
+    }
+
+    // Operations
+
+    pub async fn status(&self) -> Result<BranStatus> {
+        self.get(&["status"]).await
+    }
+
+    pub async fn health(&self) -> Result<()> {
+        let request = self.request(Method::GET, &["healthz"]);
+
+        self.execute(request).await.map(|_| ())
+    }
+
+    /// The readiness checks. A bran that is not ready is not an error.
+    pub async fn readiness(&self) -> Result<Readiness> {
+        let request = self.request(Method::GET, &["readyz"]);
+
+        Ok(request.send().await?.json().await?)
+    }
+
+    pub async fn version(&self) -> Result<VersionInfo> {
+        self.get(&["version"]).await
+    }
+
+    /// The Prometheus metrics, in the text format.
+    pub async fn metrics(&self) -> Result<String> {
+        let request = self.request(Method::GET, &["metrics"]);
+
+        Ok(self.execute(request).await?.text().await?)
+    }
+
+    fn url(&self, segments: &[&str]) -> Url {
+        let mut url = self.base.clone();
+
+        // Cannot fail, the constructor only takes http(s) URLs
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.pop_if_empty().extend(segments);
+        }
+
+        url
+    }
+
+    fn request(&self, method: Method, segments: &[&str]) -> RequestBuilder {
+        let request = self.http.request(method, self.url(segments));
+
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Sends `request`, turning error statuses into an [`Error`].
+    async fn execute(&self, request: RequestBuilder) -> Result<Response> {
+        let response = request.send().await?;
+        let status = response.status();
+
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        // bran explains most errors in a `msg` field
+        let body = response.text().await.unwrap_or_default();
+        let msg = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|v| v["msg"].as_str().map(str::to_owned))
+            .unwrap_or_else(|| {
+                if body.is_empty() {
+                    status.canonical_reason().unwrap_or_default().to_owned()
+                } else {
+                    body
+                }
+            });
+
+        Err(Error::from_status(status, msg))
+    }
+
+    async fn get<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<T> {
+        let request = self.request(Method::GET, segments);
+
+        decode(self.execute(request).await?).await
+    }
+
+    async fn send<B: Serialize + ?Sized>(
+        &self,
+        method: Method,
+        segments: &[&str],
+        body: &B,
+    ) -> Result<()> {
+        let request = self.request(method, segments).json(body);
+
+        self.execute(request).await.map(|_| ())
+    }
+}
+
+async fn decode<T: DeserializeOwned>(response: Response) -> Result<T> {
+    let body = response.bytes().await?;
+
+    Ok(serde_json::from_slice(&body)?)
+}
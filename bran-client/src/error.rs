@@ -0,0 +1,35 @@
+use reqwest::StatusCode;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("invalid bran URL: {0}")]
+    Url(#[from] url::ParseError),
+    #[error("bran URL must be http or https: {0}")]
+    NotHttp(url::Url),
+    #[error("could not reach bran: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("missing or unknown API token: {0}")]
+    Unauthorized(String),
+    #[error("not allowed: {0}")]
+    Forbidden(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    /// Any other error status, with the message bran gave
+    #[error("bran answered {status}: {msg}")]
+    Api { status: StatusCode, msg: String },
+    #[error("unexpected response from bran: {0}")]
+    Decode(#[from] serde_json::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub(crate) fn from_status(status: StatusCode, msg: String) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => Error::Unauthorized(msg),
+            StatusCode::FORBIDDEN => Error::Forbidden(msg),
+            StatusCode::NOT_FOUND => Error::NotFound(msg),
+            status => Error::Api { status, msg },
+        }
+    }
+}
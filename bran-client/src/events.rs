@@ -0,0 +1,50 @@
+use futures_util::stream::{self, BoxStream};
+use reqwest::Response;
+
+use crate::error::Result;
+use crate::types::Event;
+
+pub type EventStream = BoxStream<'static, Result<Event>>;
+
+/// Reads the server-sent events of `response` as they arrive. Keep-alive
+/// comments are skipped.
+pub(crate) fn event_stream(response: Response) -> EventStream {
+    let state = (response, String::new());
+
+    let events = stream::try_unfold(state, |(mut response, mut buffer)| async move {
+        loop {
+            if let Some(end) = buffer.find("\n\n") {
+                let block = buffer[..end].to_owned();
+                buffer.drain(..end + 2);
+
+                if let Some(event) = parse_block(&block)? {
+                    return Ok(Some((event, (response, buffer))));
+                }
+                continue;
+            }
+
+            match response.chunk().await? {
+                Some(chunk) => buffer.push_str(&String::from_utf8_lossy(&chunk)),
+                None => return Ok(None),
+            }
+        }
+    });
+
+    Box::pin(events)
+}
+
+/// The event in one block of `field: value` lines, if it carries data.
+fn parse_block(block: &str) -> Result<Option<Event>> {
+    let data = block
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| data.strip_prefix(' ').unwrap_or(data))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if data.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_str(&data)?))
+}
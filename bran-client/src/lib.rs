@@ -0,0 +1,24 @@
+//! Typed async client for the bran API.
+//!
+//! ```no_run
+//! # async fn run() -> bran_client::Result<()> {
+//! let bran = bran_client::BranClient::new("http://localhost:8014")?.with_token("secret");
+//!
+//! for app in bran.list_apps().await? {
+//!     println!("{} {:?}", app.name, app.status);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod client;
+mod error;
+mod events;
+mod types;
+
+pub use client::BranClient;
+pub use error::{Error, Result};
+pub use events::EventStream;
+pub use types::*;
+
+pub use starduck;
@@ -0,0 +1,339 @@
+//! What the bran endpoints send and take.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use starduck::{AdditionOrder, Application, Directives, Status};
+use url::Url;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppSummary {
+    pub name: String,
+    /// Status of the last report, if the application ever reported
+    pub status: Option<Status>,
+}
+
+/// The dothing instance that receives the orders of an application.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DothingTarget {
+    pub url: Url,
+    /// Value sent in the `Authorization` header
+    pub auth_header: Option<String>,
+    #[serde(default)]
+    pub tls: TargetTls,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TargetTls {
+    /// PEM bundle used to verify the dothing certificate
+    pub ca_cert: Option<PathBuf>,
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+}
+
+/// Endpoint that is told about the remediation of an application.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: Url,
+    /// Key used to sign the payloads with HMAC-SHA256
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionSummary {
+    pub version: u64,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppVersion {
+    pub version: u64,
+    pub timestamp: DateTime<Utc>,
+    pub application: Application,
+    pub directives: HashMap<String, Directives>,
+}
+
+/// A value that changed between two versions, addressed by its JSON
+/// pointer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Change {
+    pub path: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProblemInfo {
+    pub location_key: String,
+    pub data_requirement_key: String,
+    pub device_uuid: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OrderKind {
+    Addition,
+    Restart,
+    Reconfigure,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AllocationStatus {
+    Pending,
+    Reconciled,
+    Failed,
+}
+
+/// A device bran ordered and is waiting to see reported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Allocation {
+    pub device_uuid: Uuid,
+    pub problem: ProblemInfo,
+    pub order: AdditionOrder,
+    pub allocated_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub status: AllocationStatus,
+}
+
+/// An order planned on the last cycle. Additions show their directive as
+/// set, they only get a device uuid once admitted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedOrder {
+    pub app_name: String,
+    pub problem: ProblemInfo,
+    pub kind: OrderKind,
+    pub target: Url,
+    /// The addition, restart or reconfigure order, as `kind` says
+    pub order: Value,
+}
+
+/// An order held back by the rate limits or an open circuit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeferredOrder {
+    pub app_name: String,
+    pub problem: ProblemInfo,
+    pub kind: OrderKind,
+    pub deferred_since: DateTime<Utc>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreaker {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub failure_threshold: u32,
+    #[serde(rename = "probe_interval")]
+    pub probe_interval_secs: i64,
+    pub opened_at: Option<DateTime<Utc>>,
+    pub last_probe: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Reporter,
+    Operator,
+    Admin,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub client: SocketAddr,
+    pub identity: Option<String>,
+    pub role: Option<Role>,
+    pub method: String,
+    pub path: String,
+    pub app: Option<String>,
+    pub location: Option<String>,
+    pub status: u16,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// Every application of the register with its directives, as exported by
+/// `/register/export`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterSnapshot {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub apps: BTreeMap<String, AppSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppSnapshot {
+    pub application: Application,
+    #[serde(default)]
+    pub status: Option<Application>,
+    #[serde(default)]
+    pub directives: HashMap<String, Directives>,
+    #[serde(default)]
+    pub target: Option<DothingTarget>,
+    #[serde(default)]
+    pub webhook: Option<Webhook>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Applications in the snapshot overwrite theirs, the rest are kept
+    #[default]
+    Merge,
+    /// The register ends up holding only the snapshot
+    Replace,
+}
+
+/// What an import changed, or would change on a dry run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub msg: String,
+    pub dry_run: bool,
+    pub changes: Vec<Change>,
+}
+
+/// Orders approved by `/planner/deferred/:app/approve`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalReport {
+    pub msg: String,
+    pub approved: Vec<DeferredOrder>,
+}
+
+/// The checks behind `/readyz`. bran is ready when all of them pass.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Readiness {
+    pub storage: bool,
+    pub planner: bool,
+    pub circuit: bool,
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.storage && self.planner && self.circuit
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub name: String,
+    pub version: String,
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleSummary {
+    pub number: u64,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: i64,
+    pub orders: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranStatus {
+    pub apps: AppCounts,
+    pub planner: PlannerStatus,
+    pub deferred_orders: usize,
+    pub allocations: AllocationCounts,
+    pub circuits: HashMap<String, CircuitState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppCounts {
+    pub registered: usize,
+    pub reporting: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannerStatus {
+    pub interval_secs: i64,
+    pub running: bool,
+    pub last_cycle: Option<CycleSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationCounts {
+    pub pending: usize,
+    pub failed: usize,
+}
+
+/// A change in the register or the planner, as streamed on `/events`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventKind {
+    AppRegistered {
+        app: String,
+    },
+    AppUpdated {
+        app: String,
+    },
+    AppRemoved {
+        app: String,
+    },
+    StatusChanged {
+        app: String,
+        from: Option<Status>,
+        to: Status,
+    },
+    DirectivesChanged {
+        app: String,
+    },
+    TargetChanged {
+        app: String,
+    },
+    PlannerCycleStarted,
+    PlannerCycleFinished {
+        duration_ms: i64,
+        orders: usize,
+    },
+    OrderSent {
+        app: String,
+        problem: ProblemInfo,
+        kind: OrderKind,
+    },
+    OrderFailed {
+        app: String,
+        problem: ProblemInfo,
+        kind: OrderKind,
+        error: String,
+    },
+    EscalationExhausted {
+        app: String,
+        problem: ProblemInfo,
+    },
+    CircuitOpened {
+        target: String,
+        apps: Vec<String>,
+    },
+    /// Sent by a newer bran than this client knows
+    #[serde(other)]
+    Unknown,
+}
@@ -1,23 +1,146 @@
 use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
 
-use serde::Deserialize;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use starduck::{Application, Directives};
 
+use super::{DothingTarget, History, Managed, Webhook};
+use crate::config::RegisterConfig;
+
 type AppName = String;
 type LocationKey = String;
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ApplicationRegister {
-    pub apps: HashMap<AppName, Application>,
+    /// Desired state, only changed through the objective API
+    pub specs: HashMap<AppName, Application>,
+    /// Observed state, as last reported by the monitors
+    pub statuses: HashMap<AppName, Application>,
+    /// Request id of the last state report of each application
+    pub report_ids: HashMap<AppName, String>,
     pub directives: HashMap<AppName, HashMap<LocationKey, Directives>>,
+    pub targets: HashMap<AppName, DothingTarget>,
+    pub webhooks: HashMap<AppName, Webhook>,
+    pub history: History,
+    /// Applications loaded from the manifests directory
+    #[serde(default)]
+    pub managed: HashMap<AppName, Managed>,
 }
 
 impl ApplicationRegister {
-    pub fn new() -> Self {
+    pub fn new(config: &RegisterConfig) -> Self {
         // Initialize Register
         ApplicationRegister {
-            apps: HashMap::new(),
+            specs: HashMap::new(),
+            statuses: HashMap::new(),
+            report_ids: HashMap::new(),
             directives: HashMap::new(),
+            targets: HashMap::new(),
+            webhooks: HashMap::new(),
+            history: History::new(config.history_size),
+            managed: HashMap::new(),
         }
     }
+
+    /// The register saved in `config.state_file`, or an empty one when there
+    /// is nothing to restore.
+    pub fn restore(config: &RegisterConfig) -> Result<Self> {
+        let Some(path) = config.state_file.as_deref().filter(|p| p.exists()) else {
+            return Ok(Self::new(config));
+        };
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Could not read state file {}", path.display()))?;
+        let mut register: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid state file {}", path.display()))?;
+
+        register.history.set_max_versions(config.history_size);
+
+        info!(
+            "Restored {} applications from {}",
+            register.specs.len(),
+            path.display()
+        );
+
+        Ok(register)
+    }
+
+    /// Writes the register to `path`, replacing it only once fully written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+
+        fs::write(&tmp, serde_json::to_vec(self)?)
+            .with_context(|| format!("Could not write {}", tmp.display()))?;
+        fs::rename(&tmp, path).with_context(|| format!("Could not replace {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// Stores the current spec and directives of `app_name` as a new
+    /// version in the history.
+    pub fn record_version(&mut self, app_name: &str) {
+        let Some(application) = self.specs.get(app_name) else {
+            return;
+        };
+
+        let directives = self.directives.get(app_name).cloned().unwrap_or_default();
+        let version = self.history.record(app_name, application, &directives);
+
+        debug!("Recorded version {} of {}", version, app_name);
+    }
+
+    /// Puts back the spec and directives stored in `version`. Targets and
+    /// webhooks are not versioned, so the current ones are kept.
+    pub fn rollback(&mut self, app_name: &str, version: u64) -> Option<()> {
+        let stored = self.history.get(app_name, version)?.clone();
+
+        self.specs.insert(app_name.to_owned(), stored.application);
+        self.directives
+            .insert(app_name.to_owned(), stored.directives);
+        self.record_version(app_name);
+
+        Some(())
+    }
+
+    /// Every application as in [`Self::app_view`], by name.
+    pub fn view(&self) -> Value {
+        self.specs
+            .keys()
+            .map(|name| (name.clone(), self.app_view(name)))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    /// Everything the register holds for `app_name` as one JSON document.
+    pub fn app_view(&self, app_name: &str) -> Value {
+        let mut view = self.app_view_with_secrets(app_name);
+        view["target"] = json!(self.targets.get(app_name).map(|t| t.redacted()));
+        view["webhook"] = json!(self.webhooks.get(app_name).map(|w| w.redacted()));
+
+        view
+    }
+
+    /// [`Self::view`] with the secrets left in, only to be compared with
+    /// [`diff_redacted`](super::diff_redacted).
+    pub fn view_with_secrets(&self) -> Value {
+        self.specs
+            .keys()
+            .map(|name| (name.clone(), self.app_view_with_secrets(name)))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    /// [`Self::app_view`] with the secrets left in.
+    pub fn app_view_with_secrets(&self, app_name: &str) -> Value {
+        json!({
+            "application": self.specs.get(app_name),
+            "status": self.statuses.get(app_name),
+            "directives": self.directives.get(app_name),
+            "target": self.targets.get(app_name),
+            "webhook": self.webhooks.get(app_name),
+        })
+    }
 }
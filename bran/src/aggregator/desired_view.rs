@@ -0,0 +1,67 @@
+use starduck::{Application, Location, Status};
+
+/// Lays the desired `count` and `required` of every data requirement in
+/// `spec` over what the monitors reported in `observed`. Locations and data
+/// requirements missing from the report show up with no components, and the
+/// ones the spec doesn't ask for are dropped.
+pub fn desired_view(spec: &Application, observed: &Application) -> Application {
+    let mut view = observed.clone();
+    view.locations = overlay(&spec.locations, Some(&observed.locations));
+
+    view
+}
+
+/// Whether some data requirement in the view has fewer components than the
+/// spec asks for.
+pub fn has_shortfall(location: &Location) -> bool {
+    location
+        .data_requirements
+        .values()
+        .any(|data_req| data_req.count > data_req.components.len())
+        || location.locations.values().any(has_shortfall)
+}
+
+fn overlay(spec: &Location, observed: Option<&Location>) -> Location {
+    let mut view = match observed {
+        Some(observed) => observed.clone(),
+        None => {
+            let mut location = spec.clone();
+            location.status = Status::Uninitialized;
+            location
+        }
+    };
+
+    view.data_requirements = spec
+        .data_requirements
+        .iter()
+        .map(|(key, spec_req)| {
+            let data_req = match observed.and_then(|o| o.data_requirements.get(key)) {
+                Some(observed_req) => {
+                    let mut data_req = observed_req.clone();
+                    data_req.count = spec_req.count;
+                    data_req.required = spec_req.required;
+                    data_req
+                }
+                None => {
+                    let mut data_req = spec_req.clone();
+                    data_req.components.clear();
+                    data_req.status = Status::Uninitialized;
+                    data_req
+                }
+            };
+
+            (key.clone(), data_req)
+        })
+        .collect();
+
+    view.locations = spec
+        .locations
+        .iter()
+        .map(|(key, spec_loc)| {
+            let observed_loc = observed.and_then(|o| o.locations.get(key));
+            (key.clone(), overlay(spec_loc, observed_loc))
+        })
+        .collect();
+
+    view
+}
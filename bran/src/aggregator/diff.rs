@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+const REDACTED: &str = "<redacted>";
+
+/// Fields of an application view that hold credentials.
+const SECRET_FIELDS: [&str; 2] = ["/target/auth_header", "/webhook/secret"];
+
+/// A value that changed between two JSON documents, addressed by its
+/// JSON pointer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Change {
+    pub path: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+pub fn diff(before: &Value, after: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_into("", Some(before), Some(after), &mut changes);
+
+    changes
+}
+
+/// Like [`diff`], for views that hold secrets. A changed secret is still
+/// reported, with both of its values redacted.
+pub fn diff_redacted(before: &Value, after: &Value) -> Vec<Change> {
+    let mut changes = diff(before, after);
+
+    for change in &mut changes {
+        for value in [&mut change.before, &mut change.after]
+            .into_iter()
+            .flatten()
+        {
+            redact(&change.path, value);
+        }
+    }
+
+    changes
+}
+
+fn redact(path: &str, value: &mut Value) {
+    if SECRET_FIELDS.iter().any(|field| path.ends_with(field)) {
+        *value = json!(REDACTED);
+        return;
+    }
+
+    if let Value::Object(fields) = value {
+        for (key, field) in fields.iter_mut().filter(|(_, v)| !v.is_null()) {
+            let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+            redact(&child, field);
+        }
+    }
+}
+
+fn diff_into(path: &str, before: Option<&Value>, after: Option<&Value>, changes: &mut Vec<Change>) {
+    match (before, after) {
+        (Some(Value::Object(b)), Some(Value::Object(a))) => {
+            let mut keys = b.keys().chain(a.keys()).collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                diff_into(&child, b.get(key), a.get(key), changes);
+            }
+        }
+        (b, a) if b != a => changes.push(Change {
+            path: path.to_owned(),
+            before: b.filter(|v| !v.is_null()).cloned(),
+            after: a.filter(|v| !v.is_null()).cloned(),
+        }),
+        _ => {}
+    }
+}
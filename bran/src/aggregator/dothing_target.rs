@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+
+use anyhow::{bail, Context, Result};
+use reqwest::{header::AUTHORIZATION, Certificate, Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+const REDACTED: &str = "<redacted>";
+
+/// One client per target and TLS settings, so the orders sent to a target
+/// share its connections. A changed CA file is read on the next restart.
+static CLIENTS: LazyLock<Mutex<HashMap<(Url, TargetTls), Client>>> =
+    LazyLock::new(Default::default);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TargetTls {
+    /// PEM bundle used to verify the dothing certificate
+    pub ca_cert: Option<PathBuf>,
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+}
+
+/// The dothing instance that receives the orders of an application.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DothingTarget {
+    pub url: Url,
+    /// Value sent in the `Authorization` header
+    pub auth_header: Option<String>,
+    #[serde(default)]
+    pub tls: TargetTls,
+}
+
+impl DothingTarget {
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            auth_header: None,
+            tls: TargetTls::default(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self.url.scheme() {
+            "http" | "https" => {}
+            scheme => bail!("Unsupported scheme {} for dothing target", scheme),
+        }
+
+        if let Some(path) = &self.tls.ca_cert {
+            if !path.is_file() {
+                bail!("CA certificate {} does not exist", path.display());
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn post(&self, endpoint: &str) -> Result<RequestBuilder> {
+        let url = format!("{}{}", self.url.as_str().trim_end_matches('/'), endpoint);
+
+        let mut builder = self.client()?.post(url);
+
+        if let Some(auth) = &self.auth_header {
+            builder = builder.header(AUTHORIZATION, auth);
+        }
+
+        Ok(builder)
+    }
+
+    fn client(&self) -> Result<Client> {
+        let key = (self.url.clone(), self.tls.clone());
+
+        if let Some(client) = CLIENTS.lock().unwrap().get(&key) {
+            return Ok(client.clone());
+        }
+
+        let client = self.build_client()?;
+        CLIENTS.lock().unwrap().insert(key, client.clone());
+
+        Ok(client)
+    }
+
+    fn build_client(&self) -> Result<Client> {
+        let mut builder =
+            Client::builder().danger_accept_invalid_certs(self.tls.accept_invalid_certs);
+
+        if let Some(path) = &self.tls.ca_cert {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("Could not read {}", path.display()))?;
+            builder = builder.add_root_certificate(Certificate::from_pem(&pem)?);
+        }
+
+        Ok(builder.build()?)
+    }
+
+    /// Copy safe to hand out through the API.
+    pub fn redacted(&self) -> Self {
+        let mut target = self.clone();
+        target.auth_header = target.auth_header.map(|_| REDACTED.to_owned());
+
+        target
+    }
+
+    /// Puts back the auth header of `current` if this one was redacted.
+    pub fn keep_secret(&mut self, current: Option<&Self>) -> Result<()> {
+        if self.auth_header.as_deref() != Some(REDACTED) {
+            return Ok(());
+        }
+
+        match current.and_then(|t| t.auth_header.clone()) {
+            Some(auth_header) => self.auth_header = Some(auth_header),
+            None => bail!("The dothing auth header is redacted and there is none to keep"),
+        }
+
+        Ok(())
+    }
+}
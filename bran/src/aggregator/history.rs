@@ -0,0 +1,94 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use starduck::{Application, Directives};
+
+type AppName = String;
+type LocationKey = String;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppVersion {
+    pub version: u64,
+    pub timestamp: DateTime<Utc>,
+    pub application: Application,
+    pub directives: HashMap<LocationKey, Directives>,
+}
+
+impl AppVersion {
+    pub fn view(&self) -> Value {
+        json!({
+            "application": self.application,
+            "directives": self.directives,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionSummary {
+    pub version: u64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// The last versions of every application and its directives.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct History {
+    max_versions: usize,
+    versions: HashMap<AppName, VecDeque<AppVersion>>,
+}
+
+impl History {
+    pub fn new(max_versions: usize) -> Self {
+        Self {
+            max_versions,
+            versions: HashMap::new(),
+        }
+    }
+
+    pub fn set_max_versions(&mut self, max_versions: usize) {
+        self.max_versions = max_versions;
+    }
+
+    pub fn record(
+        &mut self,
+        app_name: &str,
+        application: &Application,
+        directives: &HashMap<LocationKey, Directives>,
+    ) -> u64 {
+        let versions = self.versions.entry(app_name.to_owned()).or_default();
+
+        let version = versions.back().map_or(1, |v| v.version + 1);
+
+        versions.push_back(AppVersion {
+            version,
+            timestamp: Utc::now(),
+            application: application.clone(),
+            directives: directives.clone(),
+        });
+
+        while versions.len() > self.max_versions.max(1) {
+            versions.pop_front();
+        }
+
+        version
+    }
+
+    pub fn list(&self, app_name: &str) -> Option<Vec<VersionSummary>> {
+        self.versions.get(app_name).map(|versions| {
+            versions
+                .iter()
+                .map(|v| VersionSummary {
+                    version: v.version,
+                    timestamp: v.timestamp,
+                })
+                .collect()
+        })
+    }
+
+    pub fn get(&self, app_name: &str, version: u64) -> Option<&AppVersion> {
+        self.versions
+            .get(app_name)
+            .and_then(|versions| versions.iter().find(|v| v.version == version))
+    }
+}
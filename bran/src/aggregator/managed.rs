@@ -0,0 +1,41 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::ApplicationRegister;
+
+/// Parts of an application that come from a manifest file, and so can't
+/// be changed through the API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Managed {
+    pub source: PathBuf,
+    /// Whether the manifest also holds the objective. Directives always
+    /// come from it.
+    pub spec: bool,
+}
+
+impl ApplicationRegister {
+    /// The manifest holding the objective of `app_name`, if any.
+    pub fn managed_spec(&self, app_name: &str) -> Option<&Path> {
+        self.managed
+            .get(app_name)
+            .filter(|m| m.spec)
+            .map(|m| m.source.as_path())
+    }
+
+    /// The manifest holding the directives of `app_name`, if any.
+    pub fn managed_directives(&self, app_name: &str) -> Option<&Path> {
+        self.managed.get(app_name).map(|m| m.source.as_path())
+    }
+
+    /// Forgets what a manifest declared about `app_name`, and its state.
+    /// The history, dothing target and webhook are set through the API, so
+    /// they are kept.
+    pub fn remove_app(&mut self, app_name: &str) {
+        self.specs.remove(app_name);
+        self.statuses.remove(app_name);
+        self.report_ids.remove(app_name);
+        self.directives.remove(app_name);
+        self.managed.remove(app_name);
+    }
+}
@@ -1,3 +1,17 @@
 mod application_register;
+mod desired_view;
+mod diff;
+mod dothing_target;
+mod history;
+mod managed;
+mod register_snapshot;
+mod webhook;
 
 pub use application_register::ApplicationRegister;
+pub use desired_view::{desired_view, has_shortfall};
+pub use diff::{diff, diff_redacted, Change};
+pub use dothing_target::DothingTarget;
+pub use history::History;
+pub use managed::Managed;
+pub use register_snapshot::{location_problems, ImportMode, RegisterSnapshot};
+pub use webhook::Webhook;
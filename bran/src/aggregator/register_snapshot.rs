@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use starduck::{Application, Directives, Location};
+
+use super::{ApplicationRegister, DothingTarget, Webhook};
+
+type AppName = String;
+type LocationKey = String;
+
+/// Format of the snapshots written by this build. Bumped whenever a
+/// snapshot could no longer be read the same way.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Every application of the register with its directives, in a format meant
+/// to outlive bran versions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterSnapshot {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub apps: BTreeMap<AppName, AppSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppSnapshot {
+    pub application: Application,
+    /// Last reported state, if the application ever reported
+    #[serde(default)]
+    pub status: Option<Application>,
+    #[serde(default)]
+    pub directives: HashMap<LocationKey, Directives>,
+    #[serde(default)]
+    pub target: Option<DothingTarget>,
+    #[serde(default)]
+    pub webhook: Option<Webhook>,
+}
+
+/// How an imported snapshot combines with what is in the register.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Applications in the snapshot overwrite theirs, the rest are kept
+    #[default]
+    Merge,
+    /// The register ends up holding only the snapshot
+    Replace,
+}
+
+impl RegisterSnapshot {
+    pub fn parse(content: &str) -> Result<Self> {
+        let snapshot: Self = serde_json::from_str(content)?;
+
+        if snapshot.version > SNAPSHOT_VERSION {
+            bail!(
+                "Snapshot version {} is newer than the supported {}",
+                snapshot.version,
+                SNAPSHOT_VERSION
+            );
+        }
+
+        Ok(snapshot)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}", path.display()))?;
+
+        Self::parse(&content).with_context(|| format!("Invalid snapshot {}", path.display()))
+    }
+
+    /// The snapshot without the dothing credentials and webhook secrets.
+    /// Importing it keeps the secrets already in the register.
+    pub fn redacted(mut self) -> Self {
+        for app in self.apps.values_mut() {
+            app.target = app.target.as_ref().map(DothingTarget::redacted);
+            app.webhook = app.webhook.as_ref().map(Webhook::redacted);
+        }
+
+        self
+    }
+
+    /// Problems that would make the snapshot load into a register the API
+    /// could never have produced.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for (name, app) in &self.apps {
+            if app.application.name != *name {
+                problems.push(format!(
+                    "App {} holds an application named {}",
+                    name, app.application.name
+                ));
+            }
+
+            problems.extend(
+                location_problems(&app.application.locations, "root")
+                    .into_iter()
+                    .map(|p| format!("App {}: {}", name, p)),
+            );
+
+            for location in app.directives.keys() {
+                if app.application.locations.get(location).is_none() {
+                    problems.push(format!(
+                        "App {} has directives for unknown location {}",
+                        name, location
+                    ));
+                }
+            }
+
+            if let Some(target) = &app.target {
+                if let Err(e) = target.validate() {
+                    problems.push(format!("App {}: {}", name, e));
+                }
+            }
+
+            if let Some(webhook) = &app.webhook {
+                if let Err(e) = webhook.validate() {
+                    problems.push(format!("App {}: {}", name, e));
+                }
+            }
+        }
+
+        problems
+    }
+}
+
+/// Locations the planner would skip. It only looks for problems in leaves,
+/// so data requirements next to sublocations are never checked.
+pub fn location_problems(location: &Location, key: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if !location.locations.is_empty() && !location.data_requirements.is_empty() {
+        problems.push(format!(
+            "Location {} has both sublocations and data requirements",
+            key
+        ));
+    }
+
+    for (key, sublocation) in &location.locations {
+        problems.extend(location_problems(sublocation, key));
+    }
+
+    problems
+}
+
+impl ApplicationRegister {
+    pub fn snapshot(&self) -> RegisterSnapshot {
+        let apps = self
+            .specs
+            .iter()
+            .map(|(name, application)| {
+                let app = AppSnapshot {
+                    application: application.clone(),
+                    status: self.statuses.get(name).cloned(),
+                    directives: self.directives.get(name).cloned().unwrap_or_default(),
+                    target: self.targets.get(name).cloned(),
+                    webhook: self.webhooks.get(name).cloned(),
+                };
+
+                (name.clone(), app)
+            })
+            .collect();
+
+        RegisterSnapshot {
+            version: SNAPSHOT_VERSION,
+            exported_at: Utc::now(),
+            apps,
+        }
+    }
+
+    /// Loads `snapshot` into the register. Every imported application gets
+    /// a new version in the history. What manifests declare is kept as
+    /// loaded, and applications loaded from them are kept when replacing.
+    /// Secrets left redacted keep their current value.
+    pub fn import(&mut self, snapshot: RegisterSnapshot, mode: ImportMode) -> Result<()> {
+        // Redacted secrets stand for the ones the register holds
+        let mut apps = snapshot.apps;
+        for (name, app) in &mut apps {
+            if let Some(target) = app.target.as_mut() {
+                target
+                    .keep_secret(self.targets.get(name))
+                    .with_context(|| format!("App {name}"))?;
+            }
+            if let Some(webhook) = app.webhook.as_mut() {
+                webhook
+                    .keep_secret(self.webhooks.get(name))
+                    .with_context(|| format!("App {name}"))?;
+            }
+        }
+
+        if mode == ImportMode::Replace {
+            let managed = self.managed.clone();
+            let kept = |name: &String| managed.contains_key(name);
+
+            self.specs.retain(|name, _| kept(name));
+            self.statuses.retain(|name, _| kept(name));
+            self.report_ids.retain(|name, _| kept(name));
+            self.directives.retain(|name, _| kept(name));
+            self.targets.retain(|name, _| kept(name));
+            self.webhooks.retain(|name, _| kept(name));
+        }
+
+        for (name, app) in apps {
+            self.report_ids.remove(&name);
+
+            // Directives always come from the manifest, the objective may
+            if let Some(source) = self.managed_directives(&name) {
+                debug!("Keeping what {} declares about {}", source.display(), name);
+
+                if self.managed_spec(&name).is_none() {
+                    self.specs.insert(name.clone(), app.application);
+                }
+            } else {
+                self.specs.insert(name.clone(), app.application);
+
+                // Apps without directives have no entry, as when registered
+                if app.directives.is_empty() {
+                    self.directives.remove(&name);
+                } else {
+                    self.directives.insert(name.clone(), app.directives);
+                }
+            }
+
+            match app.status {
+                Some(status) => self.statuses.insert(name.clone(), status),
+                None => self.statuses.remove(&name),
+            };
+            match app.target {
+                Some(target) => self.targets.insert(name.clone(), target),
+                None => self.targets.remove(&name),
+            };
+            match app.webhook {
+                Some(webhook) => self.webhooks.insert(name.clone(), webhook),
+                None => self.webhooks.remove(&name),
+            };
+
+            self.record_version(&name);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use url::Url;
+
+const REDACTED: &str = "<redacted>";
+const SIGNATURE_HEADER: &str = "X-Bran-Signature";
+/// A receiver that hangs would otherwise hold its delivery task forever
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Shared by every webhook, so deliveries reuse connections.
+static CLIENT: LazyLock<Client> = LazyLock::new(|| {
+    // Only fails without a TLS backend, which reqwest always builds with
+    Client::builder().timeout(DELIVERY_TIMEOUT).build().unwrap()
+});
+
+/// Endpoint that is told about the remediation of an application.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: Url,
+    /// Key used to sign the payloads with HMAC-SHA256
+    pub secret: Option<String>,
+}
+
+impl Webhook {
+    pub fn validate(&self) -> Result<()> {
+        match self.url.scheme() {
+            "http" | "https" => Ok(()),
+            scheme => bail!("Unsupported scheme {} for webhook", scheme),
+        }
+    }
+
+    /// Posts `body` once, giving up after 10 seconds. The signature goes in
+    /// the `X-Bran-Signature` header as `sha256=<hex digest>`.
+    pub async fn deliver(&self, body: &[u8]) -> Result<()> {
+        let mut request = CLIENT
+            .post(self.url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_vec());
+
+        if let Some(secret) = &self.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, body)?);
+        }
+
+        request.send().await?.error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Copy safe to hand out through the API.
+    pub fn redacted(&self) -> Self {
+        let mut webhook = self.clone();
+        webhook.secret = webhook.secret.map(|_| REDACTED.to_owned());
+
+        webhook
+    }
+
+    /// Puts back the secret of `current` if this one was redacted.
+    pub fn keep_secret(&mut self, current: Option<&Self>) -> Result<()> {
+        if self.secret.as_deref() != Some(REDACTED) {
+            return Ok(());
+        }
+
+        match current.and_then(|w| w.secret.clone()) {
+            Some(secret) => self.secret = Some(secret),
+            None => bail!("The webhook secret is redacted and there is none to keep"),
+        }
+
+        Ok(())
+    }
+}
+
+fn sign(secret: &str, body: &[u8]) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+    mac.update(body);
+
+    let digest = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+
+    Ok(format!("sha256={}", digest))
+}
@@ -0,0 +1,125 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard};
+
+use crate::aggregator::Change;
+use crate::auth::Role;
+use crate::config::AuditConfig;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub client: SocketAddr,
+    pub identity: Option<String>,
+    pub role: Option<Role>,
+    pub method: String,
+    pub path: String,
+    pub app: Option<String>,
+    pub location: Option<String>,
+    pub status: u16,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    pub app: Option<String>,
+    pub location: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.app
+            .as_deref()
+            .is_none_or(|a| entry.app.as_deref() == Some(a))
+            && self
+                .location
+                .as_deref()
+                .is_none_or(|l| entry.location.as_deref() == Some(l))
+            && self.since.is_none_or(|t| entry.timestamp >= t)
+    }
+}
+
+/// Append-only JSON lines file holding every mutation of the register, and
+/// every request denied by the API authentication.
+pub struct AuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+    writing: AsyncMutex<()>,
+}
+
+impl AuditLog {
+    pub fn from_config(config: &AuditConfig) -> Result<Self> {
+        Self::open(config.log.clone())
+    }
+
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Could not open audit log {}", path.display()))?;
+
+        info!("Writing audit log to {}", path.display());
+
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+            writing: AsyncMutex::new(()),
+        })
+    }
+
+    /// Held by a request changing the register from before it runs until
+    /// its entry is written, so no other request changes it in between.
+    pub async fn exclusive(&self) -> AsyncMutexGuard<'_, ()> {
+        self.writing.lock().await
+    }
+
+    pub fn append(&self, entry: &AuditEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let mut file = self.file.lock().unwrap();
+        file.write_all(line.as_bytes())?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    /// Whether the log file is still there to be written to.
+    pub fn is_reachable(&self) -> bool {
+        self.path.is_file() && self.file.lock().is_ok_and(|f| f.metadata().is_ok())
+    }
+
+    /// Entries matching `query`, newest last.
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        let file = File::open(&self.path)?;
+
+        let mut entries = Vec::new();
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+
+            match serde_json::from_str::<AuditEntry>(&line) {
+                Ok(entry) if query.matches(&entry) => entries.push(entry),
+                Ok(_) => {}
+                Err(e) => warn!("Skipping malformed audit entry: {e}"),
+            }
+        }
+
+        if let Some(limit) = query.limit {
+            let skip = entries.len().saturating_sub(limit);
+            entries.drain(..skip);
+        }
+
+        Ok(entries)
+    }
+}
@@ -0,0 +1,92 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use serde_json::Value;
+
+use axum::{
+    extract::{ConnectInfo, RawPathParams, Request},
+    http::Method,
+    middleware::Next,
+    response::Response,
+    Extension,
+};
+
+use super::{AuditEntry, AuditLog};
+use crate::aggregator::{diff_redacted, ApplicationRegister};
+use crate::auth::Identity;
+
+const APP_PARAM: &str = "app";
+const LOCATION_PARAM: &str = "loc";
+/// The one request not about a single application that changes the register
+const IMPORT_PATH: &str = "/register/import";
+
+/// Writes an audit entry for every request that can change the register,
+/// with the difference it made to the targeted application. Those requests
+/// run one at a time, so a difference holds the changes of its request only.
+pub async fn record_mutations(
+    Extension(audit): Extension<Arc<AuditLog>>,
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    identity: Option<Extension<Identity>>,
+    params: Option<RawPathParams>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+
+    if !matches!(
+        method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    ) {
+        return next.run(request).await;
+    }
+
+    let path = request.uri().path().to_owned();
+
+    let param = |name: &str| {
+        params.as_ref().and_then(|p| {
+            p.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_owned())
+        })
+    };
+    let app = param(APP_PARAM);
+    let location = param(LOCATION_PARAM);
+
+    // Imports may touch every application, the other requests without one
+    // leave the register alone
+    let snapshot = |app: &Option<String>| {
+        let guard = app_reg.lock().unwrap();
+        match app {
+            Some(app) => guard.app_view_with_secrets(app),
+            None if path == IMPORT_PATH => guard.view_with_secrets(),
+            None => Value::Null,
+        }
+    };
+
+    let _exclusive = audit.exclusive().await;
+
+    let before = snapshot(&app);
+    let response = next.run(request).await;
+    let after = snapshot(&app);
+
+    let entry = AuditEntry {
+        timestamp: Utc::now(),
+        client: addr,
+        identity: identity.as_ref().map(|Extension(i)| i.name.clone()),
+        role: identity.as_ref().map(|Extension(i)| i.role),
+        method: method.to_string(),
+        path,
+        app,
+        location,
+        status: response.status().as_u16(),
+        changes: diff_redacted(&before, &after),
+    };
+
+    if let Err(e) = audit.append(&entry) {
+        error!("Could not write audit entry: {e}");
+    }
+
+    response
+}
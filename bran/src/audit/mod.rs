@@ -0,0 +1,5 @@
+mod audit_log;
+mod middleware;
+
+pub use audit_log::{AuditEntry, AuditLog, AuditQuery};
+pub use middleware::record_mutations;
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use axum::http::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::config::AuthConfig;
+
+/// Roles are ordered, each one can do everything the previous one can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Reporter,
+    Operator,
+    Admin,
+}
+
+impl Role {
+    /// Role needed to use `method` on the API. Reporters may only PUT
+    /// state, the one route taking a PUT. Operators read everything and
+    /// manage objectives and directives, admins delete.
+    pub fn required_for(method: &Method) -> Self {
+        match *method {
+            Method::PUT => Role::Reporter,
+            Method::DELETE => Role::Admin,
+            _ => Role::Operator,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Identity {
+    pub name: String,
+    pub role: Role,
+}
+
+#[derive(Deserialize)]
+struct TokenEntry {
+    name: String,
+    token: String,
+    role: Role,
+}
+
+/// Checks API tokens against the ones listed in the tokens file.
+pub struct Authenticator {
+    tokens: Option<HashMap<String, Identity>>,
+}
+
+impl Authenticator {
+    /// Loads the configured tokens file. Without it every request is let
+    /// through.
+    pub fn from_config(config: &AuthConfig) -> Result<Self> {
+        match &config.tokens_file {
+            Some(path) => Self::from_file(path),
+            None => {
+                warn!("No tokens file set. API authentication is disabled");
+                Ok(Self::disabled())
+            }
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read tokens file {}", path.display()))?;
+
+        let entries: Vec<TokenEntry> = serde_json::from_str(&content)
+            .with_context(|| format!("Could not parse tokens file {}", path.display()))?;
+
+        info!("Loaded {} API tokens", entries.len());
+
+        let tokens = entries
+            .into_iter()
+            .map(|e| {
+                let identity = Identity {
+                    name: e.name,
+                    role: e.role,
+                };
+                (e.token, identity)
+            })
+            .collect();
+
+        Ok(Self {
+            tokens: Some(tokens),
+        })
+    }
+
+    pub fn disabled() -> Self {
+        Self { tokens: None }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.tokens.is_some()
+    }
+
+    pub fn identify(&self, token: &str) -> Option<&Identity> {
+        self.tokens.as_ref().and_then(|t| t.get(token))
+    }
+}
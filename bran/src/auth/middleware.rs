@@ -0,0 +1,104 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde_json::json;
+
+use axum::{
+    extract::{ConnectInfo, Json, Request},
+    http::{header::AUTHORIZATION, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension,
+};
+
+use super::{Authenticator, Identity, Role};
+use crate::audit::{AuditEntry, AuditLog};
+
+const BEARER: &str = "Bearer ";
+
+/// Rejects requests without a token allowed to use the route. The caller's
+/// identity is left in the request extensions for the handlers.
+pub async fn authorize(
+    Extension(authenticator): Extension<Arc<Authenticator>>,
+    Extension(audit): Extension<Arc<AuditLog>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if !authenticator.is_enabled() {
+        return next.run(request).await;
+    }
+
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix(BEARER));
+
+    let identity = match token.and_then(|t| authenticator.identify(t)) {
+        Some(identity) => identity.clone(),
+        None => {
+            let msg = "Missing or unknown API token".to_owned();
+            return deny(&audit, addr, &request, None, StatusCode::UNAUTHORIZED, msg);
+        }
+    };
+
+    let required = Role::required_for(request.method());
+
+    if identity.role < required {
+        let msg = format!("{:?} role required", required);
+        return deny(
+            &audit,
+            addr,
+            &request,
+            Some(&identity),
+            StatusCode::FORBIDDEN,
+            msg,
+        );
+    }
+
+    request.extensions_mut().insert::<Identity>(identity);
+
+    next.run(request).await
+}
+
+/// Answers a denied request, writing it to the audit log.
+fn deny(
+    audit: &AuditLog,
+    addr: SocketAddr,
+    request: &Request,
+    identity: Option<&Identity>,
+    status: StatusCode,
+    msg: String,
+) -> Response {
+    let method = request.method();
+    let path = request.uri().path();
+
+    match identity {
+        Some(identity) => warn!(
+            "Denied {} {} from {} as {}: {}",
+            method, path, addr, identity.name, msg
+        ),
+        None => warn!("Denied {} {} from {}: {}", method, path, addr, msg),
+    }
+
+    let entry = AuditEntry {
+        timestamp: Utc::now(),
+        client: addr,
+        identity: identity.map(|i| i.name.clone()),
+        role: identity.map(|i| i.role),
+        method: method.to_string(),
+        path: path.to_owned(),
+        app: None,
+        location: None,
+        status: status.as_u16(),
+        changes: Vec::new(),
+    };
+
+    if let Err(e) = audit.append(&entry) {
+        error!("Could not write audit entry: {e}");
+    }
+
+    (status, Json(json!({"msg": msg}))).into_response()
+}
@@ -0,0 +1,5 @@
+mod authenticator;
+mod middleware;
+
+pub use authenticator::{Authenticator, Identity, Role};
+pub use middleware::authorize;
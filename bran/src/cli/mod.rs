@@ -0,0 +1,113 @@
+mod plan;
+mod register;
+mod replay;
+mod simulate;
+mod validate;
+
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use url::Url;
+
+use crate::config::ConfigArgs;
+
+pub use plan::plan;
+pub use register::{export, import};
+pub use replay::{replay, replay_simulated};
+pub use simulate::simulate;
+pub use validate::{validate, FileKind};
+
+/// Planner and register of the self-adaptive IoT applications.
+#[derive(Debug, Parser)]
+#[command(name = "bran", version)]
+pub struct Cli {
+    #[command(flatten)]
+    pub config: ConfigArgs,
+
+    /// Print the resulting config, secrets redacted, and exit
+    #[arg(long)]
+    pub print_config: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Default, Subcommand)]
+pub enum Command {
+    /// Run the API and the planner (the default)
+    #[default]
+    Serve,
+    /// Print the orders the planner would send for a register snapshot,
+    /// built as they would go out. Fails if one can't be built.
+    /// Applications need a dothing target, from the snapshot or the config.
+    Plan {
+        /// Snapshot, as written by `bran export`
+        state: PathBuf,
+    },
+    /// Replay recorded state updates through the planner on a virtual
+    /// clock and print the orders it would send, cycle by cycle. Interval,
+    /// limits and timeouts come from the config.
+    Simulate {
+        /// Snapshot with the applications and directives to start from
+        state: PathBuf,
+        /// State updates, one JSON object with `at`, `app` and `state` per
+        /// line
+        updates: PathBuf,
+        /// Stop after this time, an addition timeout past the last update
+        /// by default
+        #[arg(long)]
+        until: Option<DateTime<Utc>>,
+    },
+    /// Send the requests recorded in a journal to a bran again, or run the
+    /// state reports in it through the simulator
+    Replay {
+        /// Journal file, or a journal directory to replay every file of
+        journal: PathBuf,
+        /// bran to send the requests to
+        #[arg(
+            long,
+            required_unless_present = "simulate",
+            conflicts_with = "simulate"
+        )]
+        url: Option<Url>,
+        /// API token to send the requests with
+        #[arg(long)]
+        token: Option<String>,
+        /// Keep the recorded time between requests, divided by this factor
+        #[arg(long, requires = "url")]
+        speed: Option<f64>,
+        /// Simulate over this snapshot instead. Objectives and directives
+        /// come from it, only the state reports are replayed
+        #[arg(long)]
+        simulate: Option<PathBuf>,
+        /// Stop the simulation after this time
+        #[arg(long, requires = "simulate")]
+        until: Option<DateTime<Utc>>,
+    },
+    /// Check an application, directive or snapshot file. Applications in
+    /// a snapshot need a dothing target unless the config has a default.
+    Validate {
+        file: PathBuf,
+        /// What the file holds, guessed when left out
+        #[arg(long, value_enum)]
+        kind: Option<FileKind>,
+        /// Application the directives must fit
+        #[arg(long)]
+        app: Option<PathBuf>,
+    },
+    /// Print the register saved in `register.state_file` as a snapshot
+    Export {
+        /// Write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Load a snapshot into the register saved in `register.state_file`.
+    /// Stop bran first, it overwrites the file when it shuts down.
+    Import {
+        file: PathBuf,
+        /// Drop the applications missing from the snapshot
+        #[arg(long)]
+        replace: bool,
+    },
+}
@@ -0,0 +1,72 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use crate::aggregator::{ApplicationRegister, ImportMode, RegisterSnapshot};
+use crate::config::Config;
+use crate::events::EventBus;
+use crate::metrics::Metrics;
+use crate::planner::{Planner, PlannerState};
+use crate::shutdown::Shutdown;
+
+/// Runs the planning half of a cycle on `state`, builds the orders and
+/// prints them. No order is sent. Fails if any order can't be built.
+pub fn plan(config: &Config, state: &Path) -> Result<()> {
+    let snapshot = RegisterSnapshot::read(state)?;
+
+    let mut register = ApplicationRegister::new(&config.register);
+    register.import(snapshot, ImportMode::Replace)?;
+
+    let (_, updates) = watch::channel(config.planner.clone());
+    // A fixed namespace, so every run hands out the same device uuids
+    let planner_state = Arc::new(Mutex::new(PlannerState::with_namespace(Uuid::nil())));
+    let mut planner = Planner::new(
+        Arc::new(Mutex::new(register)),
+        Arc::clone(&planner_state),
+        Arc::new(EventBus::new(&config.events)),
+        Arc::new(Metrics::new()?),
+        updates,
+        Shutdown::new(),
+    );
+
+    let planned = planner.plan();
+    let allocations = &planner_state.lock().unwrap().allocations;
+
+    let mut failed = 0;
+    let orders = planned
+        .iter()
+        .map(|planned| {
+            let mut order = json!({
+                "app": planned.app_name,
+                "problem": planned.problem,
+                "kind": planned.order.kind(),
+                "target": planned.target.url,
+            });
+
+            match planned.build(allocations) {
+                Ok(built) => {
+                    order["problem"] = json!(built.problem);
+                    order["order"] = json!(built.order);
+                }
+                Err(e) => {
+                    failed += 1;
+                    order["error"] = json!(format!("{e:#}"));
+                }
+            }
+
+            order
+        })
+        .collect::<Vec<Value>>();
+
+    println!("{}", serde_json::to_string_pretty(&orders)?);
+
+    if failed > 0 {
+        bail!("{} of {} orders can't be built", failed, orders.len());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::aggregator::{ApplicationRegister, ImportMode, RegisterSnapshot};
+use crate::config::RegisterConfig;
+
+fn state_file(config: &RegisterConfig) -> Result<&PathBuf> {
+    config
+        .state_file
+        .as_ref()
+        .context("register.state_file is not set, there is no saved register")
+}
+
+pub fn export(config: &RegisterConfig, output: Option<&Path>) -> Result<()> {
+    let path = state_file(config)?;
+
+    if !path.exists() {
+        anyhow::bail!("No register saved in {} yet", path.display());
+    }
+
+    let register = ApplicationRegister::restore(config)?;
+    let rendered = serde_json::to_string_pretty(&register.snapshot())?;
+
+    match output {
+        Some(output) => fs::write(output, rendered)
+            .with_context(|| format!("Could not write {}", output.display()))?,
+        None => println!("{rendered}"),
+    }
+
+    Ok(())
+}
+
+pub fn import(config: &RegisterConfig, file: &Path, mode: ImportMode) -> Result<()> {
+    let path = state_file(config)?;
+    let snapshot = RegisterSnapshot::read(file)?;
+    let count = snapshot.apps.len();
+
+    let mut register = ApplicationRegister::restore(config)?;
+    register.import(snapshot, mode)?;
+    register.save(path)?;
+
+    eprintln!("Imported {} applications into {}", count, path.display());
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use reqwest::{Method, StatusCode};
+use url::Url;
+
+use super::simulate::print_timeline;
+use crate::config::Config;
+use crate::journal::read_journal;
+
+/// Sends the requests of `journal` to the bran at `url`, in the order they
+/// were recorded. With `speed` the recorded time between them is kept,
+/// divided by it.
+pub async fn replay(
+    journal: &Path,
+    url: &Url,
+    token: Option<&str>,
+    speed: Option<f64>,
+) -> Result<()> {
+    if speed.is_some_and(|s| !s.is_finite() || s <= 0.0) {
+        bail!("--speed must be a positive number");
+    }
+
+    let entries = read_journal(journal)?;
+    let client = reqwest::Client::new();
+    let base = url.as_str().trim_end_matches('/');
+
+    let mut previous: Option<DateTime<Utc>> = None;
+    let (mut sent, mut skipped, mut different) = (0, 0, 0);
+
+    for entry in &entries {
+        // Targets and webhooks would come back with their secrets redacted
+        if entry.redacted {
+            warn!(
+                "Skipping {} {}, its secrets were not recorded",
+                entry.method, entry.path
+            );
+            skipped += 1;
+            continue;
+        }
+
+        if let (Some(speed), Some(previous)) = (speed, previous) {
+            let gap = (entry.at - previous).to_std().unwrap_or_default();
+            tokio::time::sleep(gap.div_f64(speed)).await;
+        }
+        previous = Some(entry.at);
+
+        let method = Method::from_bytes(entry.method.as_bytes())?;
+        let mut request = client.request(method, format!("{}{}", base, entry.path));
+
+        if !entry.body.is_null() {
+            request = request.json(&entry.body);
+        }
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+
+        let status = request.send().await?.status();
+        sent += 1;
+
+        if status != StatusCode::from_u16(entry.status)? {
+            warn!(
+                "{} {} got {}, it got {} when recorded",
+                entry.method, entry.path, status, entry.status
+            );
+            different += 1;
+        }
+    }
+
+    eprintln!(
+        "Replayed {} requests to {}, {} answered differently, {} skipped",
+        sent, url, different, skipped
+    );
+
+    Ok(())
+}
+
+/// Runs the state reports of `journal` that bran accepted through the
+/// simulator, over the register in `state`, and prints the orders.
+pub async fn replay_simulated(
+    config: &Config,
+    journal: &Path,
+    state: &Path,
+    until: Option<DateTime<Utc>>,
+) -> Result<()> {
+    let entries = read_journal(journal)?;
+
+    let updates = entries
+        .iter()
+        .filter(|entry| StatusCode::from_u16(entry.status).is_ok_and(|s| s.is_success()))
+        .filter_map(|entry| entry.state_update())
+        .collect::<Vec<_>>();
+
+    let left_out = entries.len() - updates.len();
+    if left_out > 0 {
+        eprintln!(
+            "Left out {} requests that are not accepted state reports, objectives and directives come from {}",
+            left_out,
+            state.display()
+        );
+    }
+
+    print_timeline(config, state, updates, until).await
+}
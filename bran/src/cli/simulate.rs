@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use crate::aggregator::RegisterSnapshot;
+use crate::config::Config;
+use crate::simulation::{read_updates, Simulator, StateUpdate};
+
+/// Replays the state updates in `updates` over the register in `state` and
+/// prints the orders the planner would send, cycle by cycle.
+pub async fn simulate(
+    config: &Config,
+    state: &Path,
+    updates: &Path,
+    until: Option<DateTime<Utc>>,
+) -> Result<()> {
+    print_timeline(config, state, read_updates(updates)?, until).await
+}
+
+pub(super) async fn print_timeline(
+    config: &Config,
+    state: &Path,
+    updates: Vec<StateUpdate>,
+    until: Option<DateTime<Utc>>,
+) -> Result<()> {
+    let snapshot = RegisterSnapshot::read(state)?;
+
+    let timeline = Simulator::new(snapshot, config.planner.clone())?
+        .run(updates, until)
+        .await;
+
+    println!("{}", serde_json::to_string_pretty(&timeline)?);
+
+    Ok(())
+}
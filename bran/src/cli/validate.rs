@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use starduck::{AdditionOrder, Application, Directives, ReconfigureOrder, RestartOrder};
+
+use crate::aggregator::{location_problems, RegisterSnapshot};
+use crate::config::Config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FileKind {
+    /// Application, as sent to `/apps/:app`
+    App,
+    /// Directives of every location, as returned by `/directives/:app`
+    Directives,
+    /// Addition directive, as sent to `/directives/addition/:app/:loc`
+    Addition,
+    /// Reconfigure directive, as sent to `/directives/reconfig/:app/:loc`
+    Reconfig,
+    /// Restart directive, as sent to `/directives/restart/:app/:loc`
+    Restart,
+    /// Register snapshot, as written by `bran export`
+    Snapshot,
+}
+
+impl FileKind {
+    fn name(&self) -> String {
+        self.to_possible_value()
+            .map(|v| v.get_name().to_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Tried in this order when the kind is not given. Stricter formats go
+/// first, since the directives accept almost any object.
+const GUESS_ORDER: [FileKind; 6] = [
+    FileKind::Snapshot,
+    FileKind::App,
+    FileKind::Addition,
+    FileKind::Reconfig,
+    FileKind::Restart,
+    FileKind::Directives,
+];
+
+/// Checks `file` and prints what is wrong with it. Fails if anything is.
+pub fn validate(
+    config: &Config,
+    file: &Path,
+    kind: Option<FileKind>,
+    app: Option<&Path>,
+) -> Result<()> {
+    let value = read_value(file)?;
+
+    let kind = match kind {
+        Some(kind) => kind,
+        None => GUESS_ORDER
+            .into_iter()
+            .find(|kind| check(config, *kind, value.clone(), None).is_ok())
+            .with_context(|| format!("{} is not a file bran knows", file.display()))?,
+    };
+
+    let app = app
+        .map(|path| parse::<Application>(read_value(path)?))
+        .transpose()
+        .context("Invalid application given with --app")?;
+
+    let problems = check(config, kind, value, app.as_ref())
+        .with_context(|| format!("{} is not a valid {} file", file.display(), kind.name()))?;
+
+    if problems.is_empty() {
+        println!("{}: valid {} file", file.display(), kind.name());
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{}: {}", file.display(), problem);
+    }
+
+    bail!("Found {} problems in {}", problems.len(), file.display())
+}
+
+fn check(
+    config: &Config,
+    kind: FileKind,
+    value: Value,
+    app: Option<&Application>,
+) -> Result<Vec<String>> {
+    let problems = match kind {
+        FileKind::App => location_problems(&parse::<Application>(value)?.locations, "root"),
+        FileKind::Directives => {
+            let directives = parse::<HashMap<String, Directives>>(value)?;
+
+            match app {
+                Some(app) => directives
+                    .keys()
+                    .filter(|location| app.locations.get(location).is_none())
+                    .map(|location| format!("Location {} is not in app {}", location, app.name))
+                    .collect(),
+                None => Vec::new(),
+            }
+        }
+        FileKind::Addition => parse::<AdditionOrder>(value).map(|_| Vec::new())?,
+        FileKind::Reconfig => parse::<ReconfigureOrder>(value).map(|_| Vec::new())?,
+        FileKind::Restart => parse::<RestartOrder>(value).map(|_| Vec::new())?,
+        FileKind::Snapshot => {
+            let snapshot = RegisterSnapshot::parse(&serde_json::to_string(&value)?)?;
+            let mut problems = snapshot.problems();
+
+            // Orders of these would have nowhere to go
+            if config.planner.dothing.is_none() {
+                problems.extend(
+                    snapshot
+                        .apps
+                        .iter()
+                        .filter(|(_, app)| app.target.is_none())
+                        .map(|(name, _)| {
+                            format!("App {} has no dothing target and there is no default", name)
+                        }),
+                );
+            }
+
+            problems
+        }
+    };
+
+    Ok(problems)
+}
+
+fn parse<T: DeserializeOwned>(value: Value) -> Result<T> {
+    Ok(serde_json::from_value(value)?)
+}
+
+/// JSON, or YAML for files ending in .yaml or .yml.
+fn read_value(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+
+    let value = match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml" | "yml") => serde_yaml::from_str(&content)?,
+        _ => serde_json::from_str(&content)?,
+    };
+
+    Ok(value)
+}
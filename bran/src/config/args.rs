@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use url::Url;
+
+use super::LogFormat;
+
+/// Settings that can be given on the command line. They take precedence over
+/// the config file and the environment.
+#[derive(Debug, Clone, Default, Args)]
+pub struct ConfigArgs {
+    /// TOML or YAML config file
+    #[arg(long, short = 'c')]
+    pub config: Option<PathBuf>,
+
+    /// File of KEY=value lines loaded into the environment first
+    #[arg(long)]
+    pub env_file: Option<PathBuf>,
+
+    #[arg(long)]
+    pub port: Option<u16>,
+
+    /// Default dothing instance
+    #[arg(long)]
+    pub dothing: Option<Url>,
+
+    /// Seconds between planner cycles
+    #[arg(long)]
+    pub interval: Option<u64>,
+
+    /// Filter in the `RUST_LOG` syntax
+    #[arg(long)]
+    pub log_level: Option<String>,
+
+    #[arg(long, value_parser = parse_format)]
+    pub log_format: Option<LogFormat>,
+}
+
+fn parse_format(format: &str) -> Result<LogFormat, String> {
+    match format {
+        "text" => Ok(LogFormat::Text),
+        "json" => Ok(LogFormat::Json),
+        other => Err(format!("unknown log format {other}. Use text or json")),
+    }
+}
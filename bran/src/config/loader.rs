@@ -0,0 +1,208 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+
+use super::{Config, ConfigArgs};
+
+const CONFIG_FILE: &str = "BRAN_CONFIG";
+
+/// What an environment variable holds. Env vars are plain strings, so the
+/// ones going to numeric or boolean settings are parsed first.
+#[derive(Clone, Copy)]
+enum EnvKind {
+    Text,
+    Number,
+    Bool,
+}
+
+/// Environment variables bran has always read, and where they go in the
+/// config.
+const ENV_KEYS: [(&str, &str, EnvKind); 32] = [
+    ("PORT", "server.port", EnvKind::Number),
+    (
+        "shutdown_timeout",
+        "server.shutdown_timeout_secs",
+        EnvKind::Number,
+    ),
+    ("tls_cert", "server.tls.cert", EnvKind::Text),
+    ("tls_key", "server.tls.key", EnvKind::Text),
+    ("tls_client_ca", "server.tls.client_ca", EnvKind::Text),
+    ("auth_tokens", "auth.tokens_file", EnvKind::Text),
+    ("audit_log", "audit.log", EnvKind::Text),
+    ("journal_dir", "journal.dir", EnvKind::Text),
+    (
+        "journal_max_bytes",
+        "journal.max_file_bytes",
+        EnvKind::Number,
+    ),
+    ("journal_max_files", "journal.max_files", EnvKind::Number),
+    ("history_size", "register.history_size", EnvKind::Number),
+    ("state_file", "register.state_file", EnvKind::Text),
+    ("manifests_dir", "register.manifests_dir", EnvKind::Text),
+    ("event_buffer", "events.buffer", EnvKind::Number),
+    ("webhook_retries", "webhooks.retries", EnvKind::Number),
+    ("webhook_backoff", "webhooks.backoff_secs", EnvKind::Number),
+    ("dothing", "planner.dothing", EnvKind::Text),
+    ("dothing_auth", "planner.dothing_auth", EnvKind::Text),
+    ("watcher_delay", "planner.delay_secs", EnvKind::Number),
+    ("watcher_interval", "planner.interval_secs", EnvKind::Number),
+    (
+        "addition_timeout",
+        "planner.addition_timeout_secs",
+        EnvKind::Number,
+    ),
+    (
+        "require_approval",
+        "planner.require_approval",
+        EnvKind::Bool,
+    ),
+    (
+        "max_actions_per_cycle",
+        "planner.limits.max_actions_per_cycle",
+        EnvKind::Number,
+    ),
+    (
+        "max_in_flight",
+        "planner.limits.max_in_flight",
+        EnvKind::Number,
+    ),
+    (
+        "rate_window",
+        "planner.limits.rate_window_secs",
+        EnvKind::Number,
+    ),
+    (
+        "max_actions_per_app",
+        "planner.limits.max_actions_per_app",
+        EnvKind::Number,
+    ),
+    (
+        "max_actions_per_location",
+        "planner.limits.max_actions_per_location",
+        EnvKind::Number,
+    ),
+    (
+        "circuit_failure_threshold",
+        "planner.circuit.failure_threshold",
+        EnvKind::Number,
+    ),
+    (
+        "circuit_probe_interval",
+        "planner.circuit.probe_interval_secs",
+        EnvKind::Number,
+    ),
+    ("RUST_LOG", "logging.level", EnvKind::Text),
+    ("log_format", "logging.format", EnvKind::Text),
+    ("otlp_endpoint", "logging.otlp_endpoint", EnvKind::Text),
+];
+
+/// Builds the config from the defaults, then the config file, then the
+/// environment, then the command line. The result is validated.
+pub fn load(args: &ConfigArgs) -> Result<Config> {
+    if let Some(path) = &args.env_file {
+        dotenv::from_path(path)
+            .with_context(|| format!("Could not load env file {}", path.display()))?;
+    }
+
+    let mut config = serde_json::to_value(Config::default())?;
+
+    if let Some(path) = config_file(args) {
+        merge(&mut config, read_file(&path)?);
+    }
+
+    for (var, key, kind) in ENV_KEYS {
+        if let Ok(value) = env::var(var) {
+            set(&mut config, key, coerce(var, value, kind)?)?;
+        }
+    }
+
+    let overrides = [
+        ("server.port", args.port.map(|p| json!(p))),
+        ("planner.dothing", args.dothing.as_ref().map(|u| json!(u))),
+        ("planner.interval_secs", args.interval.map(|i| json!(i))),
+        ("logging.level", args.log_level.as_ref().map(|l| json!(l))),
+        ("logging.format", args.log_format.map(|f| json!(f))),
+    ];
+
+    for (key, value) in overrides {
+        if let Some(value) = value {
+            set(&mut config, key, value)?;
+        }
+    }
+
+    let config: Config = serde_json::from_value(config).context("Invalid configuration")?;
+    config.validate()?;
+
+    Ok(config)
+}
+
+/// The config file given on the command line or in `BRAN_CONFIG`.
+pub fn config_file(args: &ConfigArgs) -> Option<PathBuf> {
+    args.config
+        .clone()
+        .or_else(|| env::var(CONFIG_FILE).ok().map(Into::into))
+}
+
+/// Parses a config file as TOML or YAML, going by its extension.
+pub fn read_file(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read config file {}", path.display()))?;
+
+    let value = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&content)?,
+        Some("yaml" | "yml") => serde_yaml::from_str(&content)?,
+        _ => bail!(
+            "Config file {} must end in .toml, .yaml or .yml",
+            path.display()
+        ),
+    };
+
+    Ok(value)
+}
+
+fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                merge(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+fn set(config: &mut Value, key: &str, value: Value) -> Result<()> {
+    let mut node = config;
+
+    for part in key.split('.') {
+        if node.is_null() {
+            *node = json!({});
+        }
+
+        let Value::Object(table) = node else {
+            bail!("Cannot set {key}, the config file has a value where a table of it should be");
+        };
+
+        node = table.entry(part).or_insert(Value::Null);
+    }
+
+    *node = value;
+
+    Ok(())
+}
+
+fn coerce(var: &str, value: String, kind: EnvKind) -> Result<Value> {
+    match kind {
+        EnvKind::Text => Ok(Value::String(value)),
+        EnvKind::Number => match value.trim().parse::<u64>() {
+            Ok(number) => Ok(json!(number)),
+            Err(_) => bail!("{var} must be a whole number, got {value:?}"),
+        },
+        EnvKind::Bool => match value.trim().parse::<bool>() {
+            Ok(flag) => Ok(json!(flag)),
+            Err(_) => bail!("{var} must be true or false, got {value:?}"),
+        },
+    }
+}
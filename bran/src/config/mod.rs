@@ -0,0 +1,12 @@
+mod args;
+mod loader;
+mod reload;
+mod settings;
+
+pub use args::ConfigArgs;
+pub use loader::{config_file, load};
+pub use reload::Reloader;
+pub use settings::{
+    AuditConfig, AuthConfig, CircuitConfig, Config, EventsConfig, JournalConfig, LimitsConfig,
+    LogFormat, LoggingConfig, PlannerConfig, RegisterConfig, TlsConfig, WebhooksConfig,
+};
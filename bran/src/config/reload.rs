@@ -0,0 +1,143 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{mpsc, watch};
+
+use super::{load, Config, ConfigArgs, PlannerConfig};
+use crate::aggregator::diff;
+use crate::telemetry::LogLevel;
+
+/// Writes to a config file usually come in bursts.
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// Applies the settings that are safe to change while bran runs: the whole
+/// planner section (interval, rate limits, default target, circuit) and the
+/// log level. Everything else needs a restart.
+pub struct Reloader {
+    args: ConfigArgs,
+    current: Config,
+    planner: watch::Sender<PlannerConfig>,
+    log_level: LogLevel,
+}
+
+impl Reloader {
+    pub fn new(
+        args: ConfigArgs,
+        current: Config,
+        planner: watch::Sender<PlannerConfig>,
+        log_level: LogLevel,
+    ) -> Self {
+        Self {
+            args,
+            current,
+            planner,
+            log_level,
+        }
+    }
+
+    /// Reloads on SIGHUP and whenever the config file changes.
+    pub async fn run(mut self, file: Option<PathBuf>) {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(k) => Some(k),
+            Err(e) => {
+                error!("Could not listen for SIGHUP, config won't be reloaded on it: {e}");
+                None
+            }
+        };
+
+        let (tx, mut changed) = mpsc::channel(1);
+
+        let _watcher = file.as_ref().and_then(|path| {
+            let watcher =
+                notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                    if event.is_ok_and(|e| e.kind.is_modify() || e.kind.is_create()) {
+                        let _ = tx.try_send(());
+                    }
+                });
+
+            // Editors replace the file, so watch the directory holding it
+            let dir = path.parent().filter(|d| !d.as_os_str().is_empty());
+            let dir = dir.map(PathBuf::from).unwrap_or(PathBuf::from("."));
+
+            match watcher.and_then(|mut w| w.watch(&dir, RecursiveMode::NonRecursive).map(|_| w)) {
+                Ok(w) => {
+                    info!("Watching {} for changes", path.display());
+                    Some(w)
+                }
+                Err(e) => {
+                    error!("Could not watch {}: {e}", path.display());
+                    None
+                }
+            }
+        });
+
+        loop {
+            tokio::select! {
+                Some(_) = async { hangup.as_mut()?.recv().await } => {
+                    info!("SIGHUP received. Reloading config");
+                }
+                Some(_) = changed.recv() => {
+                    tokio::time::sleep(SETTLE_TIME).await;
+                    while changed.try_recv().is_ok() {}
+
+                    info!("Config file changed. Reloading config");
+                }
+                else => return,
+            }
+
+            self.reload();
+        }
+    }
+
+    /// Loads the config again. On any error the running config stays.
+    fn reload(&mut self) {
+        let new = match load(&self.args) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("Config reload failed, keeping the old config: {e:#}");
+                return;
+            }
+        };
+
+        let before = serde_json::to_value(self.current.redacted()).unwrap_or_default();
+        let after = serde_json::to_value(new.redacted()).unwrap_or_default();
+        let changes = diff(&before, &after);
+
+        if changes.is_empty() {
+            info!("Config reloaded without changes");
+            return;
+        }
+
+        for change in &changes {
+            let reloadable = change.path.starts_with("/planner") || change.path == "/logging/level";
+
+            if !reloadable {
+                warn!("{} changed but needs a restart to apply", change.path);
+                continue;
+            }
+
+            info!(
+                "{} changed from {} to {}",
+                change.path,
+                change.before.clone().unwrap_or_default(),
+                change.after.clone().unwrap_or_default()
+            );
+        }
+
+        if new.logging.level != self.current.logging.level {
+            if let Err(e) = self.log_level.set(&new.logging.level) {
+                error!("Could not change the log level, keeping the old one: {e}");
+                return;
+            }
+            self.current.logging.level = new.logging.level;
+        }
+
+        // The planner picks the new section up as a whole on its next cycle
+        if changes.iter().any(|c| c.path.starts_with("/planner")) {
+            self.current.planner = new.planner.clone();
+            self.planner.send_replace(new.planner);
+        }
+    }
+}
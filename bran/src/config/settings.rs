@@ -0,0 +1,352 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::aggregator::DothingTarget;
+
+const REDACTED: &str = "<redacted>";
+
+/// Every setting of bran. Sections missing from the config file keep their
+/// defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub auth: AuthConfig,
+    pub audit: AuditConfig,
+    pub journal: JournalConfig,
+    pub register: RegisterConfig,
+    pub events: EventsConfig,
+    pub webhooks: WebhooksConfig,
+    pub planner: PlannerConfig,
+    pub logging: LoggingConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub port: u16,
+    /// Time given to requests, the planner cycle and in-flight orders to
+    /// finish on shutdown
+    pub shutdown_timeout_secs: u64,
+    pub tls: TlsConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+    /// Clients must present a certificate signed by this CA
+    pub client_ca: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// JSON list of API tokens. Authentication is off without it
+    pub tokens_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+    pub log: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JournalConfig {
+    /// Where the bodies of the state reports, objectives and directives are
+    /// recorded. Nothing is recorded without it
+    pub dir: Option<PathBuf>,
+    /// Size a journal file grows to before it is rotated
+    pub max_file_bytes: u64,
+    /// Journal files kept, the one being written included
+    pub max_files: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegisterConfig {
+    /// Versions kept per application
+    pub history_size: usize,
+    /// Where the register is saved on shutdown and restored from on start.
+    /// The planner state goes next to it, see `planner_state_file`
+    pub state_file: Option<PathBuf>,
+    /// Directory of YAML or JSON manifests with directives and objectives,
+    /// kept in sync with the register
+    pub manifests_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventsConfig {
+    /// Events kept for slow stream subscribers
+    pub buffer: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhooksConfig {
+    pub retries: u32,
+    /// Wait before the first retry, doubled on each attempt
+    pub backoff_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlannerConfig {
+    /// dothing instance for the applications without a target of their own.
+    /// Without it, every application needs one
+    pub dothing: Option<Url>,
+    /// `Authorization` header sent to the default dothing
+    pub dothing_auth: Option<String>,
+    pub delay_secs: u64,
+    pub interval_secs: u64,
+    /// Time an addition has to show up before it is flagged as failed
+    pub addition_timeout_secs: u64,
+    /// Hold every order back until an operator approves it
+    pub require_approval: bool,
+    pub limits: LimitsConfig,
+    pub circuit: CircuitConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    pub max_actions_per_cycle: Option<usize>,
+    pub max_in_flight: usize,
+    pub rate_window_secs: u64,
+    pub max_actions_per_app: Option<usize>,
+    pub max_actions_per_location: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CircuitConfig {
+    pub failure_threshold: u32,
+    pub probe_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// Filter in the `RUST_LOG` syntax
+    pub level: String,
+    pub format: LogFormat,
+    /// Only used when built with the `otlp` feature
+    pub otlp_endpoint: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            port: 8014,
+            shutdown_timeout_secs: 30,
+            tls: TlsConfig::default(),
+        }
+    }
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            log: PathBuf::from("audit.log"),
+        }
+    }
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            max_file_bytes: 10 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
+
+impl Default for RegisterConfig {
+    fn default() -> Self {
+        Self {
+            history_size: 20,
+            state_file: None,
+            manifests_dir: None,
+        }
+    }
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self { buffer: 256 }
+    }
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            backoff_secs: 2,
+        }
+    }
+}
+
+impl Default for PlannerConfig {
+    fn default() -> Self {
+        Self {
+            dothing: None,
+            dothing_auth: None,
+            delay_secs: 0,
+            interval_secs: 120,
+            addition_timeout_secs: 600,
+            require_approval: false,
+            limits: LimitsConfig::default(),
+            circuit: CircuitConfig::default(),
+        }
+    }
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_actions_per_cycle: None,
+            max_in_flight: 4,
+            rate_window_secs: 600,
+            max_actions_per_app: None,
+            max_actions_per_location: None,
+        }
+    }
+}
+
+impl Default for CircuitConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            probe_interval_secs: 60,
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "error".to_owned(),
+            format: LogFormat::Text,
+            otlp_endpoint: None,
+        }
+    }
+}
+
+impl RegisterConfig {
+    /// Where the planner state is kept, `bran-state.planner.json` for a
+    /// `bran-state.json` state file.
+    pub fn planner_state_file(&self) -> Option<PathBuf> {
+        self.state_file
+            .as_ref()
+            .map(|path| path.with_extension("planner.json"))
+    }
+}
+
+impl Config {
+    /// Checks the settings that serde can't. Reports every problem at once.
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+
+        if self.server.port == 0 {
+            errors.push("server.port can't be 0".to_owned());
+        }
+
+        let tls = &self.server.tls;
+        if tls.cert.is_some() != tls.key.is_some() {
+            errors.push("server.tls needs both cert and key".to_owned());
+        }
+        if tls.client_ca.is_some() && tls.cert.is_none() {
+            errors.push("server.tls.client_ca needs cert and key".to_owned());
+        }
+
+        if let Some(dir) = &self.register.manifests_dir {
+            if !dir.is_dir() {
+                errors.push(format!(
+                    "register.manifests_dir {} is not a directory",
+                    dir.display()
+                ));
+            }
+        }
+
+        if self.journal.max_file_bytes == 0 {
+            errors.push("journal.max_file_bytes must be at least 1".to_owned());
+        }
+        if self.journal.max_files == 0 {
+            errors.push("journal.max_files must be at least 1".to_owned());
+        }
+
+        errors.extend(self.planner.problems());
+
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.logging.level) {
+            errors.push(format!("logging.level is invalid: {e}"));
+        }
+
+        if !errors.is_empty() {
+            bail!("Invalid configuration:\n  {}", errors.join("\n  "));
+        }
+
+        Ok(())
+    }
+
+    /// Copy safe to print or hand out through the API.
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+
+        config.planner.dothing_auth = config.planner.dothing_auth.map(|_| REDACTED.to_owned());
+
+        if let Some(url) = config.planner.dothing.as_mut() {
+            if url.password().is_some() {
+                // Angle brackets would be percent encoded in the URL
+                let _ = url.set_password(Some("redacted"));
+            }
+        }
+
+        config
+    }
+}
+
+impl PlannerConfig {
+    fn problems(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.interval_secs == 0 {
+            errors.push("planner.interval_secs must be at least 1".to_owned());
+        }
+        if self.limits.max_in_flight == 0 {
+            errors.push("planner.limits.max_in_flight must be at least 1".to_owned());
+        }
+        if self.circuit.failure_threshold == 0 {
+            errors.push("planner.circuit.failure_threshold must be at least 1".to_owned());
+        }
+        if let Some(Err(e)) = self.default_target().map(|t| t.validate()) {
+            errors.push(format!("planner.dothing is invalid: {e}"));
+        }
+
+        errors
+    }
+
+    /// Target of the applications that don't have one of their own.
+    pub fn default_target(&self) -> Option<DothingTarget> {
+        self.dothing.clone().map(|url| {
+            let mut target = DothingTarget::new(url);
+            target.auth_header = self.dothing_auth.clone();
+            target
+        })
+    }
+}
@@ -0,0 +1,145 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+use serde_json::json;
+
+use axum::{
+    extract::{ConnectInfo, Json, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension,
+};
+
+use crate::aggregator::{diff_redacted, ImportMode, RegisterSnapshot};
+use crate::auth::{Authenticator, Identity, Role};
+use crate::events::EventBus;
+use crate::ApplicationRegister;
+
+#[derive(Deserialize)]
+pub struct ExportOptions {
+    /// Keep the dothing credentials and webhook secrets in the snapshot
+    #[serde(default)]
+    secrets: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ImportOptions {
+    #[serde(default)]
+    mode: ImportMode,
+    /// Only report what the import would change
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Snapshots can carry the dothing credentials and webhook secrets, so both
+/// directions are kept to admins.
+fn require_admin(
+    authenticator: &Authenticator,
+    identity: Option<&Extension<Identity>>,
+) -> Option<Response> {
+    if !authenticator.is_enabled() || identity.is_some_and(|Extension(i)| i.role >= Role::Admin) {
+        return None;
+    }
+
+    let msg = "Admin role required to export or import the register";
+    warn!("{}", msg);
+    Some((StatusCode::FORBIDDEN, Json(json!({"msg": msg}))).into_response())
+}
+
+pub async fn export_register(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    Extension(authenticator): Extension<Arc<Authenticator>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    identity: Option<Extension<Identity>>,
+    Query(options): Query<ExportOptions>,
+) -> Response {
+    info!("Get register export request from {}", addr);
+
+    if let Some(denied) = require_admin(&authenticator, identity.as_ref()) {
+        return denied;
+    }
+
+    // Without authentication anyone passes as an admin
+    if options.secrets && !authenticator.is_enabled() {
+        let msg = "Secrets are only exported to an authenticated admin";
+        warn!("{}", msg);
+        return (StatusCode::FORBIDDEN, Json(json!({"msg": msg}))).into_response();
+    }
+
+    let snapshot = app_reg.lock().unwrap().snapshot();
+    let snapshot = if options.secrets {
+        snapshot
+    } else {
+        snapshot.redacted()
+    };
+
+    info!("Exported {} applications to {}", snapshot.apps.len(), addr);
+    (StatusCode::OK, Json(snapshot)).into_response()
+}
+
+pub async fn import_register(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    Extension(events): Extension<Arc<EventBus>>,
+    Extension(authenticator): Extension<Arc<Authenticator>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    identity: Option<Extension<Identity>>,
+    Query(options): Query<ImportOptions>,
+    body: String,
+) -> Response {
+    info!(
+        "POST register import ({:?}) request from {}",
+        options.mode, addr
+    );
+
+    if let Some(denied) = require_admin(&authenticator, identity.as_ref()) {
+        return denied;
+    }
+
+    let snapshot = match RegisterSnapshot::parse(&body) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            let msg = format!("Invalid register snapshot: {e}");
+            error!("{}", msg);
+            return (StatusCode::BAD_REQUEST, Json(json!({"msg": msg}))).into_response();
+        }
+    };
+
+    let problems = snapshot.problems();
+    if !problems.is_empty() {
+        let msg = format!("Invalid register snapshot: {}", problems.join("; "));
+        error!("{}", msg);
+        return (StatusCode::BAD_REQUEST, Json(json!({"msg": msg}))).into_response();
+    }
+
+    let count = snapshot.apps.len();
+    let mut guard = app_reg.lock().unwrap();
+
+    let mut imported = guard.clone();
+    if let Err(e) = imported.import(snapshot, options.mode) {
+        let msg = format!("Could not import the register: {e}");
+        error!("{}", msg);
+        return (StatusCode::CONFLICT, Json(json!({"msg": msg}))).into_response();
+    }
+
+    let changes = diff_redacted(&guard.view_with_secrets(), &imported.view_with_secrets());
+
+    let msg = if options.dry_run {
+        format!(
+            "Importing {} applications would make {} changes",
+            count,
+            changes.len()
+        )
+    } else {
+        events.publish_changes(&guard.view(), &imported.view());
+        *guard = imported;
+        format!("Imported {} applications", count)
+    };
+
+    info!("{}", msg);
+    (
+        StatusCode::OK,
+        Json(json!({"msg": msg, "dry_run": options.dry_run, "changes": changes})),
+    )
+        .into_response()
+}
@@ -0,0 +1,30 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use serde_json::json;
+
+use axum::{
+    extract::{ConnectInfo, Json, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension,
+};
+
+use crate::audit::{AuditLog, AuditQuery};
+
+pub async fn get_audit_entries(
+    Extension(audit): Extension<Arc<AuditLog>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(query): Query<AuditQuery>,
+) -> Response {
+    info!("Get audit entries request from {}", addr);
+
+    match audit.query(&query) {
+        Ok(entries) => (StatusCode::OK, Json(entries)).into_response(),
+        Err(e) => {
+            let msg = format!("Could not read the audit log: {e}");
+            error!("{}", msg);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"msg": msg}))).into_response()
+        }
+    }
+}
@@ -2,17 +2,50 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use std::sync::Mutex;
 
+use serde::Deserialize;
 use serde_json::json;
 
 use axum::{
-    extract::{ConnectInfo, Json, Path},
+    extract::{ConnectInfo, Json, Path, Query},
     http::StatusCode,
     response::{IntoResponse, Response},
     Extension,
 };
 
+use crate::aggregator::diff;
 use crate::ApplicationRegister;
 
+#[derive(Deserialize)]
+pub struct VersionRange {
+    from: u64,
+    to: u64,
+}
+
+/// Every registered application with the status it last reported.
+pub async fn list_applications(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Response {
+    info!("Get applications request from {}", addr);
+
+    let m_app_reg = app_reg.lock().unwrap();
+
+    let mut names = m_app_reg.specs.keys().collect::<Vec<_>>();
+    names.sort();
+
+    let apps = names
+        .into_iter()
+        .map(|name| {
+            json!({
+                "name": name,
+                "status": m_app_reg.statuses.get(name).map(|app| app.status),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    (StatusCode::OK, Json(apps)).into_response()
+}
+
 pub async fn get_application(
     Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -22,7 +55,7 @@ pub async fn get_application(
 
     let m_app_reg = app_reg.lock().unwrap();
 
-    if let Some(app) = m_app_reg.apps.get(&app_name) {
+    if let Some(app) = m_app_reg.specs.get(&app_name) {
         let json_response = Json(app.clone());
 
         info!("{} info sent to {}", app_name, addr);
@@ -35,6 +68,28 @@ pub async fn get_application(
     (StatusCode::NOT_FOUND, Json(json!({"msg": err_msg}))).into_response()
 }
 
+pub async fn get_application_status(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(app_name): Path<String>,
+) -> Response {
+    info!("Get for {} status request from {}", app_name, addr);
+
+    let m_app_reg = app_reg.lock().unwrap();
+
+    if let Some(app) = m_app_reg.statuses.get(&app_name) {
+        let json_response = Json(app.clone());
+
+        info!("{} status sent to {}", app_name, addr);
+        return (StatusCode::OK, json_response).into_response();
+    }
+
+    warn!("No state reported for {app_name}");
+
+    let err_msg = format!("No state reported for {app_name}");
+    (StatusCode::NOT_FOUND, Json(json!({"msg": err_msg}))).into_response()
+}
+
 pub async fn get_application_directives(
     Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -56,3 +111,122 @@ pub async fn get_application_directives(
     let err_msg = format!("{app_name} context not found. Use lexical client to set state");
     (StatusCode::NOT_FOUND, Json(json!({"msg": err_msg}))).into_response()
 }
+
+pub async fn get_application_target(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(app_name): Path<String>,
+) -> Response {
+    info!("Get target for {} request from {}", app_name, addr);
+
+    let m_app_reg = app_reg.lock().unwrap();
+
+    if let Some(target) = m_app_reg.targets.get(&app_name) {
+        let json_response = Json(target.redacted());
+
+        info!("{} target sent to {}", app_name, addr);
+        return (StatusCode::OK, json_response).into_response();
+    }
+
+    warn!("Missing dothing target for {app_name}");
+
+    let err_msg = format!("No dothing target set for {app_name}");
+    (StatusCode::NOT_FOUND, Json(json!({"msg": err_msg}))).into_response()
+}
+
+pub async fn get_application_webhook(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(app_name): Path<String>,
+) -> Response {
+    info!("Get webhook for {} request from {}", app_name, addr);
+
+    let m_app_reg = app_reg.lock().unwrap();
+
+    if let Some(webhook) = m_app_reg.webhooks.get(&app_name) {
+        let json_response = Json(webhook.redacted());
+
+        info!("{} webhook sent to {}", app_name, addr);
+        return (StatusCode::OK, json_response).into_response();
+    }
+
+    warn!("Missing webhook for {app_name}");
+
+    let err_msg = format!("No webhook set for {app_name}");
+    (StatusCode::NOT_FOUND, Json(json!({"msg": err_msg}))).into_response()
+}
+
+pub async fn get_application_versions(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(app_name): Path<String>,
+) -> Response {
+    info!("Get versions for {} request from {}", app_name, addr);
+
+    let m_app_reg = app_reg.lock().unwrap();
+
+    if let Some(versions) = m_app_reg.history.list(&app_name) {
+        info!("{} versions sent to {}", app_name, addr);
+        return (StatusCode::OK, Json(versions)).into_response();
+    }
+
+    warn!("No versions stored for {app_name}");
+
+    let err_msg = format!("No versions stored for {app_name}");
+    (StatusCode::NOT_FOUND, Json(json!({"msg": err_msg}))).into_response()
+}
+
+pub async fn get_application_version(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((app_name, version)): Path<(String, u64)>,
+) -> Response {
+    info!(
+        "Get version {} of {} request from {}",
+        version, app_name, addr
+    );
+
+    let m_app_reg = app_reg.lock().unwrap();
+
+    if let Some(stored) = m_app_reg.history.get(&app_name, version) {
+        info!("Version {} of {} sent to {}", version, app_name, addr);
+        return (StatusCode::OK, Json(stored.clone())).into_response();
+    }
+
+    warn!("Missing version {version} of {app_name}");
+
+    let err_msg = format!("Version {version} of {app_name} not found");
+    (StatusCode::NOT_FOUND, Json(json!({"msg": err_msg}))).into_response()
+}
+
+pub async fn get_versions_diff(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(app_name): Path<String>,
+    Query(range): Query<VersionRange>,
+) -> Response {
+    info!(
+        "Get diff of {} between versions {} and {} request from {}",
+        app_name, range.from, range.to, addr
+    );
+
+    let m_app_reg = app_reg.lock().unwrap();
+
+    match (
+        m_app_reg.history.get(&app_name, range.from),
+        m_app_reg.history.get(&app_name, range.to),
+    ) {
+        (Some(from), Some(to)) => {
+            let changes = diff(&from.view(), &to.view());
+            (StatusCode::OK, Json(changes)).into_response()
+        }
+        _ => {
+            let err_msg = format!(
+                "Versions {} and {} of {} must both exist",
+                range.from, range.to, app_name
+            );
+            warn!("{}", err_msg);
+            (StatusCode::NOT_FOUND, Json(json!({"msg": err_msg}))).into_response()
+        }
+    }
+}
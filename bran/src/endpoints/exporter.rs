@@ -0,0 +1,39 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use serde_json::json;
+
+use crate::metrics::Metrics;
+use crate::ApplicationRegister;
+
+pub async fn get_metrics(
+    Extension(metrics): Extension<Arc<Metrics>>,
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+) -> Response {
+    let rendered = {
+        let guard = app_reg.lock().unwrap();
+        metrics.render(&guard)
+    };
+
+    match rendered {
+        Ok(body) => (
+            StatusCode::OK,
+            [(CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+            body,
+        )
+            .into_response(),
+        Err(e) => {
+            let msg = format!("Could not render metrics: {e}");
+            error!("{}", msg);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(json!({"msg": msg})),
+            )
+                .into_response()
+        }
+    }
+}
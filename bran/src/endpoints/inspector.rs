@@ -0,0 +1,131 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use axum::{
+    extract::{ConnectInfo, Json, Path, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension,
+};
+
+use crate::planner::PlannerState;
+
+pub async fn get_allocations(
+    Extension(planner_state): Extension<Arc<Mutex<PlannerState>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(app_name): Path<String>,
+) -> Response {
+    info!("Get allocations for {} request from {}", app_name, addr);
+
+    let guard = planner_state.lock().unwrap();
+
+    if let Some(allocations) = guard.allocations.get(&app_name) {
+        info!("{} allocations sent to {}", app_name, addr);
+        return (StatusCode::OK, Json(allocations.clone())).into_response();
+    }
+
+    let err_msg = format!("No allocations found for {app_name}");
+    warn!("{}", err_msg);
+    (StatusCode::NOT_FOUND, Json(json!({"msg": err_msg}))).into_response()
+}
+
+pub async fn get_planned_orders(
+    Extension(planner_state): Extension<Arc<Mutex<PlannerState>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Response {
+    info!("Get planned orders request from {}", addr);
+
+    let planned = planner_state.lock().unwrap().planned.clone();
+
+    (StatusCode::OK, Json(planned)).into_response()
+}
+
+pub async fn get_deferred_orders(
+    Extension(planner_state): Extension<Arc<Mutex<PlannerState>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Response {
+    info!("Get deferred orders request from {}", addr);
+
+    let deferred = planner_state.lock().unwrap().deferred.clone();
+
+    (StatusCode::OK, Json(deferred)).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct ApproveOptions {
+    /// Only approve the orders for this location
+    location: Option<String>,
+}
+
+pub async fn approve_orders(
+    Extension(planner_state): Extension<Arc<Mutex<PlannerState>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(app_name): Path<String>,
+    Query(options): Query<ApproveOptions>,
+) -> Response {
+    info!("POST for {} order approval request from {}", app_name, addr);
+
+    let approved = planner_state
+        .lock()
+        .unwrap()
+        .approve(&app_name, options.location.as_deref());
+
+    if approved.is_empty() {
+        let msg = format!("No orders of app {} are awaiting approval", app_name);
+        warn!("{}", msg);
+        return (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response();
+    }
+
+    let msg = format!(
+        "Approved {} orders of app {}, they go out on the next cycle",
+        approved.len(),
+        app_name
+    );
+    info!("{}", msg);
+    (
+        StatusCode::OK,
+        Json(json!({"msg": msg, "approved": approved})),
+    )
+        .into_response()
+}
+
+pub async fn get_circuit(
+    Extension(planner_state): Extension<Arc<Mutex<PlannerState>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Response {
+    info!("Get circuit status request from {}", addr);
+
+    let circuits = planner_state.lock().unwrap().circuits.clone();
+
+    (StatusCode::OK, Json(circuits)).into_response()
+}
+
+pub async fn clear_failed_allocation(
+    Extension(planner_state): Extension<Arc<Mutex<PlannerState>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((app_name, device_uuid)): Path<(String, Uuid)>,
+) -> Response {
+    info!("DELETE for {} allocation request from {}", app_name, addr);
+
+    let mut guard = planner_state.lock().unwrap();
+
+    if guard.allocations.clear_failed(&app_name, &device_uuid) {
+        let msg = format!(
+            "Cleared failed allocation {} in app {}",
+            device_uuid, app_name
+        );
+        info!("{}", msg);
+        return (StatusCode::OK, Json(json!({"msg": msg}))).into_response();
+    }
+
+    let msg = format!(
+        "Couldn't find failed allocation {} in app {}",
+        device_uuid, app_name
+    );
+    error!("{}", msg);
+    (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response()
+}
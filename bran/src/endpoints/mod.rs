@@ -1,22 +1,111 @@
+mod archiver;
+mod auditor;
 mod contexter;
+mod exporter;
+mod inspector;
+mod prober;
 mod receptor;
+mod streamer;
 
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
 use axum::{
-    routing::{get, post, put},
-    Router,
+    middleware,
+    routing::{delete, get, patch, post, put},
+    Extension, Router,
 };
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::services::ServeFile;
+use tower_http::trace::TraceLayer;
 
-pub(crate) fn main_router() -> Router {
+use crate::audit::{self, AuditLog};
+use crate::auth::{self, Authenticator};
+use crate::events::{self, EventBus};
+use crate::journal::{self, Journal};
+use crate::metrics::{self, Metrics};
+use crate::planner::PlannerState;
+use crate::shutdown::Shutdown;
+use crate::telemetry::{self, REQUEST_ID};
+use crate::ApplicationRegister;
+
+/// Everything the handlers and middlewares reach through extensions.
+#[derive(Clone)]
+pub struct Services {
+    pub register: Arc<Mutex<ApplicationRegister>>,
+    pub planner_state: Arc<Mutex<PlannerState>>,
+    pub events: Arc<EventBus>,
+    pub metrics: Arc<Metrics>,
+    pub authenticator: Arc<Authenticator>,
+    pub audit_log: Arc<AuditLog>,
+    /// Records the bodies the receptor handlers get, when set
+    pub journal: Option<Arc<Journal>>,
+    pub shutdown: Shutdown,
+}
+
+/// The whole bran API, ready to be served with connect info.
+pub fn router(services: Services) -> Router {
+    let api = Router::new()
+        .nest(
+            "/apps",
+            main_router().route_layer(middleware::from_fn(journal::record_bodies)),
+        )
+        .nest(
+            "/directives",
+            directives_router().route_layer(middleware::from_fn(journal::record_bodies)),
+        )
+        .nest("/planner", planner_router())
+        .nest("/register", register_router())
+        .nest("/audit", audit_router())
+        .nest("/events", events_router())
+        .merge(status_router())
+        .route_layer(middleware::from_fn(events::publish_changes))
+        .route_layer(middleware::from_fn(audit::record_mutations))
+        .layer(middleware::from_fn(auth::authorize));
+
+    let app = Router::new()
+        .nest("/", extras_router())
+        .merge(api)
+        .route_layer(middleware::from_fn(metrics::track_requests))
+        .layer(Extension(services.authenticator))
+        .layer(Extension(services.audit_log))
+        .layer(Extension(services.register))
+        .layer(Extension(services.planner_state))
+        .layer(Extension(services.events))
+        .layer(Extension(services.metrics))
+        .layer(Extension(services.shutdown));
+
+    let app = match services.journal {
+        Some(journal) => app.layer(Extension(journal)),
+        None => app,
+    };
+
+    app.layer(PropagateRequestIdLayer::new(REQUEST_ID))
+        .layer(TraceLayer::new_for_http().make_span_with(telemetry::make_request_span))
+        .layer(SetRequestIdLayer::new(REQUEST_ID, MakeRequestUuid))
+}
+
+pub fn main_router() -> Router {
     Router::new()
+        .route("/", get(contexter::list_applications))
         .route("/:app", put(receptor::update_state))
         .route("/:app", post(receptor::recieve_objective))
+        .route("/:app", patch(receptor::update_objective))
         .route("/:app", get(contexter::get_application))
+        .route("/:app/status", get(contexter::get_application_status))
+        .route("/:app/versions", get(contexter::get_application_versions))
+        .route("/:app/versions/diff", get(contexter::get_versions_diff))
+        .route(
+            "/:app/versions/:version",
+            get(contexter::get_application_version),
+        )
+        .route(
+            "/:app/rollback/:version",
+            post(receptor::rollback_application),
+        )
 }
 
-pub(crate) fn directives_router() -> Router {
+pub fn directives_router() -> Router {
     Router::new()
         .route(
             "/addition/:app/:loc",
@@ -30,12 +119,52 @@ pub(crate) fn directives_router() -> Router {
             "/restart/:app/:loc",
             post(receptor::recieve_restart_directive),
         )
+        .route("/target/:app", post(receptor::recieve_target_directive))
+        .route("/target/:app", get(contexter::get_application_target))
+        .route("/webhook/:app", post(receptor::recieve_webhook_directive))
+        .route("/webhook/:app", get(contexter::get_application_webhook))
         .route("/:app", get(contexter::get_application_directives))
 }
 
-pub(crate) fn extras_router() -> Router {
-    Router::new().route_service(
-        "/favicon.ico",
-        ServeFile::new(PathBuf::from("assets/favicon.ico")),
-    )
+pub fn planner_router() -> Router {
+    Router::new()
+        .route("/allocations/:app", get(inspector::get_allocations))
+        .route(
+            "/allocations/:app/:uuid",
+            delete(inspector::clear_failed_allocation),
+        )
+        .route("/planned", get(inspector::get_planned_orders))
+        .route("/deferred", get(inspector::get_deferred_orders))
+        .route("/deferred/:app/approve", post(inspector::approve_orders))
+        .route("/circuit", get(inspector::get_circuit))
+}
+
+pub fn register_router() -> Router {
+    Router::new()
+        .route("/export", get(archiver::export_register))
+        .route("/import", post(archiver::import_register))
+}
+
+pub fn audit_router() -> Router {
+    Router::new().route("/", get(auditor::get_audit_entries))
+}
+
+pub fn status_router() -> Router {
+    Router::new().route("/status", get(prober::get_status))
+}
+
+pub fn events_router() -> Router {
+    Router::new().route("/", get(streamer::stream_events))
+}
+
+pub fn extras_router() -> Router {
+    Router::new()
+        .route_service(
+            "/favicon.ico",
+            ServeFile::new(PathBuf::from("assets/favicon.ico")),
+        )
+        .route("/metrics", get(exporter::get_metrics))
+        .route("/healthz", get(prober::get_liveness))
+        .route("/readyz", get(prober::get_readiness))
+        .route("/version", get(prober::get_version))
 }
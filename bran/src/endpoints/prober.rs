@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use serde_json::json;
+
+use axum::{
+    extract::Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension,
+};
+
+use crate::audit::AuditLog;
+use crate::planner::{AllocationStatus, PlannerState};
+use crate::ApplicationRegister;
+
+pub async fn get_liveness() -> Response {
+    (StatusCode::OK, Json(json!({"msg": "alive"}))).into_response()
+}
+
+pub async fn get_readiness(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    Extension(planner_state): Extension<Arc<Mutex<PlannerState>>>,
+    Extension(audit): Extension<Arc<AuditLog>>,
+) -> Response {
+    let storage = !app_reg.is_poisoned() && audit.is_reachable();
+
+    let (planner, circuit) = match planner_state.lock() {
+        Ok(state) => (state.ran_recently(Utc::now()), state.circuits_closed()),
+        Err(_) => (false, false),
+    };
+
+    let checks = json!({
+        "storage": storage,
+        "planner": planner,
+        "circuit": circuit,
+    });
+
+    if storage && planner && circuit {
+        return (StatusCode::OK, Json(checks)).into_response();
+    }
+
+    warn!("Not ready: {}", checks);
+    (StatusCode::SERVICE_UNAVAILABLE, Json(checks)).into_response()
+}
+
+pub async fn get_version() -> Response {
+    let features: Vec<&str> = [("otlp", cfg!(feature = "otlp"))]
+        .into_iter()
+        .filter_map(|(name, enabled)| enabled.then_some(name))
+        .collect();
+
+    let version = json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "features": features,
+    });
+
+    (StatusCode::OK, Json(version)).into_response()
+}
+
+pub async fn get_status(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    Extension(planner_state): Extension<Arc<Mutex<PlannerState>>>,
+) -> Response {
+    let apps = {
+        let guard = app_reg.lock().unwrap();
+        json!({
+            "registered": guard.specs.len(),
+            "reporting": guard.statuses.len(),
+        })
+    };
+
+    let state = planner_state.lock().unwrap();
+
+    let circuits = state
+        .circuits
+        .iter()
+        .map(|(url, circuit)| (url.clone(), circuit.state))
+        .collect::<HashMap<_, _>>();
+
+    let status = json!({
+        "apps": apps,
+        "planner": {
+            "interval_secs": state.interval.num_seconds(),
+            "running": state.ran_recently(Utc::now()),
+            "last_cycle": state.last_cycle,
+        },
+        "deferred_orders": state.deferred.len(),
+        "allocations": {
+            "pending": state.allocations.count(AllocationStatus::Pending),
+            "failed": state.allocations.count(AllocationStatus::Failed),
+        },
+        "circuits": circuits,
+    });
+
+    (StatusCode::OK, Json(status)).into_response()
+}
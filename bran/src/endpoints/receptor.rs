@@ -7,11 +7,13 @@ use starduck::{AdditionOrder, Application, Directives, ReconfigureOrder, Restart
 
 use axum::{
     extract::{ConnectInfo, Json, Path},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Extension,
 };
 
+use crate::aggregator::{DothingTarget, Webhook};
+use crate::telemetry::REQUEST_ID;
 use crate::ApplicationRegister;
 
 pub async fn recieve_objective(
@@ -20,18 +22,58 @@ pub async fn recieve_objective(
     Path(app_name): Path<String>,
     Json(application): Json<Application>,
 ) -> Response {
-    info!("POST for {} request from {}", app_name, addr);
+    info!(app = %app_name, "POST for {} request from {}", app_name, addr);
 
     let mut guard = app_reg.lock().unwrap();
 
-    if let Some(_) = guard.apps.get(&app_name) {
-        error!("Application already registered");
+    if let Some(source) = guard.managed_spec(&app_name) {
+        return read_only(format!(
+            "The objective of {} comes from {}",
+            app_name,
+            source.display()
+        ));
+    }
+
+    if guard.specs.contains_key(&app_name) {
+        error!(app = %app_name, "Application already registered");
         return (StatusCode::BAD_REQUEST).into_response();
     }
 
-    guard.apps.insert(app_name.clone(), application.clone());
+    guard.specs.insert(app_name.clone(), application.clone());
+    guard.record_version(&app_name);
 
-    info!("{} was added to the register", app_name.clone());
+    info!(app = %app_name, "{} was added to the register", app_name.clone());
+
+    (StatusCode::OK).into_response()
+}
+
+pub async fn update_objective(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(app_name): Path<String>,
+    Json(application): Json<Application>,
+) -> Response {
+    info!(app = %app_name, "PATCH for {} request from {}", app_name, addr);
+
+    let mut guard = app_reg.lock().unwrap();
+
+    if let Some(source) = guard.managed_spec(&app_name) {
+        return read_only(format!(
+            "The objective of {} comes from {}",
+            app_name,
+            source.display()
+        ));
+    }
+
+    if !guard.specs.contains_key(&app_name) {
+        error!(app = %app_name, "Application is not in the register");
+        return (StatusCode::NOT_FOUND).into_response();
+    }
+
+    guard.specs.insert(app_name.clone(), application.clone());
+    guard.record_version(&app_name);
+
+    info!(app = %app_name, "{}'s spec was updated", app_name.clone());
 
     (StatusCode::OK).into_response()
 }
@@ -40,20 +82,27 @@ pub async fn update_state(
     Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Path(app_name): Path<String>,
+    headers: HeaderMap,
     Json(application): Json<Application>,
 ) -> Response {
-    info!("PUT for {} request from {}", app_name, addr);
+    info!(app = %app_name, "PUT for {} request from {}", app_name, addr);
 
     let mut guard = app_reg.lock().unwrap();
 
-    if let None = guard.apps.get(&app_name) {
-        error!("Application is not in the register");
+    if !guard.specs.contains_key(&app_name) {
+        error!(app = %app_name, "Application is not in the register");
         return (StatusCode::NOT_FOUND).into_response();
     }
 
-    guard.apps.insert(app_name.clone(), application.clone());
+    // Reports only touch the observed state. The spec stays as the lexical
+    // client left it
+    guard.statuses.insert(app_name.clone(), application.clone());
 
-    info!("{}'s state was updated", app_name.clone());
+    if let Some(id) = headers.get(REQUEST_ID).and_then(|id| id.to_str().ok()) {
+        guard.report_ids.insert(app_name.clone(), id.to_owned());
+    }
+
+    info!(app = %app_name, status = ?application.status, "{}'s state was updated", app_name);
 
     (StatusCode::OK).into_response()
 }
@@ -64,11 +113,26 @@ pub async fn recieve_addition_directive(
     Path((app_name, location)): Path<(String, String)>,
     Json(order): Json<AdditionOrder>,
 ) -> Response {
-    info!("POST for {} request from {}", app_name, addr);
+    info!(
+        app = %app_name,
+        location = %location,
+        order_kind = "Addition",
+        "POST for {} request from {}",
+        app_name,
+        addr
+    );
 
     let reg = app_reg.lock().unwrap().clone();
 
-    match reg.apps.get(&app_name) {
+    if let Some(source) = reg.managed_directives(&app_name) {
+        return read_only(format!(
+            "The directives of {} come from {}",
+            app_name,
+            source.display()
+        ));
+    }
+
+    match reg.specs.get(&app_name) {
         // The application exists on the register
         Some(application) => match (
             application.locations.get(&location),
@@ -77,7 +141,7 @@ pub async fn recieve_addition_directive(
             // The location exists and there are directives registered for it
             (Some(_), Some(directives)) => {
                 // There is already a directive in the location
-                if let Some(_) = directives.get(&location) {
+                if directives.contains_key(&location) {
                     app_reg
                         .lock()
                         .unwrap()
@@ -88,11 +152,19 @@ pub async fn recieve_addition_directive(
                         .unwrap()
                         .addition = Some(order);
 
+                    app_reg.lock().unwrap().record_version(&app_name);
+
                     let msg = format!(
                         "Updated addition directive in {} in app {}",
                         &location, &app_name
                     );
-                    info!("{}", msg);
+                    info!(
+                        app = %app_name,
+                        location = %location,
+                        order_kind = "Addition",
+                        "{}",
+                        msg
+                    );
                     return (StatusCode::OK, Json(json!({"msg": msg}))).into_response();
                 }
 
@@ -108,12 +180,14 @@ pub async fn recieve_addition_directive(
                     .unwrap()
                     .insert(location.clone(), directive);
 
+                app_reg.lock().unwrap().record_version(&app_name);
+
                 let msg = format!(
                     "Added addition directive in {} in app {}",
                     &location, &app_name
                 );
-                info!("{}", msg);
-                return (StatusCode::OK, Json(json!({"msg": msg}))).into_response();
+                info!(app = %app_name, location = %location, order_kind = "Addition", "{}", msg);
+                (StatusCode::OK, Json(json!({"msg": msg}))).into_response()
             }
             // The location exists but there are no directives registerd for it
             (Some(_), None) => {
@@ -129,12 +203,14 @@ pub async fn recieve_addition_directive(
                     .directives
                     .insert(app_name.clone(), directive_hash);
 
+                app_reg.lock().unwrap().record_version(&app_name);
+
                 let msg = format!(
                     "Added addition directive in {} in app {}",
                     &location, &app_name
                 );
-                info!("{}", msg);
-                return (StatusCode::OK, Json(json!({"msg": msg}))).into_response();
+                info!(app = %app_name, location = %location, order_kind = "Addition", "{}", msg);
+                (StatusCode::OK, Json(json!({"msg": msg}))).into_response()
             }
             // The location doesn't exist in the application
             (None, _) => {
@@ -142,15 +218,15 @@ pub async fn recieve_addition_directive(
                     "Couldn't find location {} in  application {}",
                     location, app_name
                 );
-                error!("{}", msg);
-                return (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response();
+                error!(app = %app_name, location = %location, order_kind = "Addition", "{}", msg);
+                (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response()
             }
         },
         // The application doesn't exist on the register
         None => {
             let msg = "Couldn't find application in register";
-            error!("{}", msg);
-            return (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response();
+            error!(app = %app_name, location = %location, order_kind = "Addition", "{}", msg);
+            (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response()
         }
     }
 }
@@ -161,11 +237,26 @@ pub async fn recieve_reconfig_directive(
     Path((app_name, location)): Path<(String, String)>,
     Json(order): Json<ReconfigureOrder>,
 ) -> Response {
-    info!("POST for {} request from {}", app_name, addr);
+    info!(
+        app = %app_name,
+        location = %location,
+        order_kind = "Reconfigure",
+        "POST for {} request from {}",
+        app_name,
+        addr
+    );
 
     let reg = app_reg.lock().unwrap().clone();
 
-    match reg.apps.get(&app_name) {
+    if let Some(source) = reg.managed_directives(&app_name) {
+        return read_only(format!(
+            "The directives of {} come from {}",
+            app_name,
+            source.display()
+        ));
+    }
+
+    match reg.specs.get(&app_name) {
         // The application exists on the register
         Some(application) => match (
             application.locations.get(&location),
@@ -174,7 +265,7 @@ pub async fn recieve_reconfig_directive(
             // The location exists and there are directives registered for it
             (Some(_), Some(directives)) => {
                 // There is already a directive in the location
-                if let Some(_) = directives.get(&location) {
+                if directives.contains_key(&location) {
                     app_reg
                         .lock()
                         .unwrap()
@@ -185,11 +276,19 @@ pub async fn recieve_reconfig_directive(
                         .unwrap()
                         .reconfig = Some(order);
 
+                    app_reg.lock().unwrap().record_version(&app_name);
+
                     let msg = format!(
                         "Updated reconfig directive in {} in app {}",
                         &location, &app_name
                     );
-                    info!("{}", msg);
+                    info!(
+                        app = %app_name,
+                        location = %location,
+                        order_kind = "Reconfigure",
+                        "{}",
+                        msg
+                    );
                     return (StatusCode::OK, Json(json!({"msg": msg}))).into_response();
                 }
 
@@ -205,12 +304,14 @@ pub async fn recieve_reconfig_directive(
                     .unwrap()
                     .insert(location.clone(), directive);
 
+                app_reg.lock().unwrap().record_version(&app_name);
+
                 let msg = format!(
                     "Added reconfig directive in {} in app {}",
                     &location, &app_name
                 );
-                info!("{}", msg);
-                return (StatusCode::OK, Json(json!({"msg": msg}))).into_response();
+                info!(app = %app_name, location = %location, order_kind = "Reconfigure", "{}", msg);
+                (StatusCode::OK, Json(json!({"msg": msg}))).into_response()
             }
             // The location exists but there are no directives registerd for it
             (Some(_), None) => {
@@ -226,12 +327,14 @@ pub async fn recieve_reconfig_directive(
                     .directives
                     .insert(app_name.clone(), directive_hash);
 
+                app_reg.lock().unwrap().record_version(&app_name);
+
                 let msg = format!(
                     "Added reconfig directive in {} in app {}",
                     &location, &app_name
                 );
-                info!("{}", msg);
-                return (StatusCode::OK, Json(json!({"msg": msg}))).into_response();
+                info!(app = %app_name, location = %location, order_kind = "Reconfigure", "{}", msg);
+                (StatusCode::OK, Json(json!({"msg": msg}))).into_response()
             }
             // The location doesn't exist in the application
             (None, _) => {
@@ -239,15 +342,21 @@ pub async fn recieve_reconfig_directive(
                     "Couldn't find location {} in  application {}",
                     location, app_name
                 );
-                error!("{}", msg);
-                return (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response();
+                error!(
+                    app = %app_name,
+                    location = %location,
+                    order_kind = "Reconfigure",
+                    "{}",
+                    msg
+                );
+                (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response()
             }
         },
         // The application doesn't exist on the register
         None => {
             let msg = "Couldn't find application in register";
-            error!("{}", msg);
-            return (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response();
+            error!(app = %app_name, location = %location, order_kind = "Reconfigure", "{}", msg);
+            (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response()
         }
     }
 }
@@ -258,11 +367,26 @@ pub async fn recieve_restart_directive(
     Path((app_name, location)): Path<(String, String)>,
     Json(order): Json<RestartOrder>,
 ) -> Response {
-    info!("POST for {} request from {}", app_name, addr);
+    info!(
+        app = %app_name,
+        location = %location,
+        order_kind = "Restart",
+        "POST for {} request from {}",
+        app_name,
+        addr
+    );
 
     let reg = app_reg.lock().unwrap().clone();
 
-    match reg.apps.get(&app_name) {
+    if let Some(source) = reg.managed_directives(&app_name) {
+        return read_only(format!(
+            "The directives of {} come from {}",
+            app_name,
+            source.display()
+        ));
+    }
+
+    match reg.specs.get(&app_name) {
         // The application exists on the register
         Some(application) => match (
             application.locations.get(&location),
@@ -271,7 +395,7 @@ pub async fn recieve_restart_directive(
             // The location exists and there are directives registered for it
             (Some(_), Some(directives)) => {
                 // There is already a directive in the location
-                if let Some(_) = directives.get(&location) {
+                if directives.contains_key(&location) {
                     app_reg
                         .lock()
                         .unwrap()
@@ -282,11 +406,13 @@ pub async fn recieve_restart_directive(
                         .unwrap()
                         .restart = Some(order);
 
+                    app_reg.lock().unwrap().record_version(&app_name);
+
                     let msg = format!(
                         "Updated restart directive in {} in app {}",
                         &location, &app_name
                     );
-                    info!("{}", msg);
+                    info!(app = %app_name, location = %location, order_kind = "Restart", "{}", msg);
                     return (StatusCode::OK, Json(json!({"msg": msg}))).into_response();
                 }
 
@@ -302,12 +428,14 @@ pub async fn recieve_restart_directive(
                     .unwrap()
                     .insert(location.clone(), directive);
 
+                app_reg.lock().unwrap().record_version(&app_name);
+
                 let msg = format!(
                     "Added restart directive in {} in app {}",
                     &location, &app_name
                 );
-                info!("{}", msg);
-                return (StatusCode::OK, Json(json!({"msg": msg}))).into_response();
+                info!(app = %app_name, location = %location, order_kind = "Restart", "{}", msg);
+                (StatusCode::OK, Json(json!({"msg": msg}))).into_response()
             }
             // The location exists but there are no directives registerd for it
             (Some(_), None) => {
@@ -323,12 +451,14 @@ pub async fn recieve_restart_directive(
                     .directives
                     .insert(app_name.clone(), directive_hash);
 
+                app_reg.lock().unwrap().record_version(&app_name);
+
                 let msg = format!(
                     "Added restart directive in {} in app {}",
                     &location, &app_name
                 );
-                info!("{}", msg);
-                return (StatusCode::OK, Json(json!({"msg": msg}))).into_response();
+                info!(app = %app_name, location = %location, order_kind = "Restart", "{}", msg);
+                (StatusCode::OK, Json(json!({"msg": msg}))).into_response()
             }
             // The location doesn't exist in the application
             (None, _) => {
@@ -336,15 +466,112 @@ pub async fn recieve_restart_directive(
                     "Couldn't find location {} in  application {}",
                     location, app_name
                 );
-                error!("{}", msg);
-                return (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response();
+                error!(app = %app_name, location = %location, order_kind = "Restart", "{}", msg);
+                (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response()
             }
         },
         // The application doesn't exist on the register
         None => {
             let msg = "Couldn't find application in register";
-            error!("{}", msg);
-            return (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response();
+            error!(app = %app_name, location = %location, order_kind = "Restart", "{}", msg);
+            (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response()
         }
     }
 }
+
+pub async fn recieve_target_directive(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(app_name): Path<String>,
+    Json(target): Json<DothingTarget>,
+) -> Response {
+    info!(app = %app_name, "POST for {} target request from {}", app_name, addr);
+
+    let mut guard = app_reg.lock().unwrap();
+
+    if !guard.specs.contains_key(&app_name) {
+        let msg = "Couldn't find application in register";
+        error!(app = %app_name, "{}", msg);
+        return (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response();
+    }
+
+    if let Err(e) = target.validate() {
+        let msg = format!("Invalid dothing target for app {}: {}", &app_name, e);
+        error!(app = %app_name, "{}", msg);
+        return (StatusCode::BAD_REQUEST, Json(json!({"msg": msg}))).into_response();
+    }
+
+    let msg = match guard.targets.insert(app_name.clone(), target) {
+        Some(_) => format!("Updated dothing target in app {}", &app_name),
+        None => format!("Added dothing target in app {}", &app_name),
+    };
+
+    info!(app = %app_name, "{}", msg);
+    (StatusCode::OK, Json(json!({"msg": msg}))).into_response()
+}
+
+pub async fn recieve_webhook_directive(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(app_name): Path<String>,
+    Json(webhook): Json<Webhook>,
+) -> Response {
+    info!(app = %app_name, "POST for {} webhook request from {}", app_name, addr);
+
+    let mut guard = app_reg.lock().unwrap();
+
+    if !guard.specs.contains_key(&app_name) {
+        let msg = "Couldn't find application in register";
+        error!(app = %app_name, "{}", msg);
+        return (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response();
+    }
+
+    if let Err(e) = webhook.validate() {
+        let msg = format!("Invalid webhook for app {}: {}", &app_name, e);
+        error!(app = %app_name, "{}", msg);
+        return (StatusCode::BAD_REQUEST, Json(json!({"msg": msg}))).into_response();
+    }
+
+    let msg = match guard.webhooks.insert(app_name.clone(), webhook) {
+        Some(_) => format!("Updated webhook in app {}", &app_name),
+        None => format!("Added webhook in app {}", &app_name),
+    };
+
+    info!(app = %app_name, "{}", msg);
+    (StatusCode::OK, Json(json!({"msg": msg}))).into_response()
+}
+
+pub async fn rollback_application(
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((app_name, version)): Path<(String, u64)>,
+) -> Response {
+    info!(app = %app_name,
+        "POST rollback of {} to version {} request from {}",
+        app_name, version, addr
+    );
+
+    let mut guard = app_reg.lock().unwrap();
+
+    if let Some(source) = guard.managed_directives(&app_name) {
+        return read_only(format!("{} comes from {}", app_name, source.display()));
+    }
+
+    if guard.rollback(&app_name, version).is_none() {
+        let msg = format!("Couldn't find version {} of {}", version, app_name);
+        error!(app = %app_name, "{}", msg);
+        return (StatusCode::NOT_FOUND, Json(json!({"msg": msg}))).into_response();
+    }
+
+    let msg = format!("Rolled back {} to version {}", app_name, version);
+    info!(app = %app_name, "{}", msg);
+    (StatusCode::OK, Json(json!({"msg": msg}))).into_response()
+}
+
+/// Answer to changes of what a manifest declares. The manifests directory
+/// is the source of truth for those.
+fn read_only(msg: String) -> Response {
+    let msg = format!("{msg}, change the manifest instead");
+    warn!("{}", msg);
+    (StatusCode::CONFLICT, Json(json!({"msg": msg}))).into_response()
+}
@@ -0,0 +1,61 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::{
+    extract::{ConnectInfo, Query},
+    response::sse::{Event as SseEvent, KeepAlive, Sse},
+    Extension,
+};
+use serde::Deserialize;
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream},
+    Stream, StreamExt,
+};
+
+use crate::events::EventBus;
+use crate::shutdown::Shutdown;
+
+#[derive(Deserialize)]
+pub struct EventFilter {
+    app: Option<String>,
+}
+
+pub async fn stream_events(
+    Extension(events): Extension<Arc<EventBus>>,
+    Extension(shutdown): Extension<Shutdown>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(filter): Query<EventFilter>,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    info!("Event stream opened by {}", addr);
+
+    let stream = BroadcastStream::new(events.subscribe()).filter_map(move |received| {
+        let event = match received {
+            Ok(event) => event,
+            Err(BroadcastStreamRecvError::Lagged(missed)) => {
+                warn!("Event stream of {} fell behind by {} events", addr, missed);
+                return None;
+            }
+        };
+
+        if !event.concerns(filter.app.as_deref()) {
+            return None;
+        }
+
+        let kind = serde_json::to_value(&event.kind).ok()?["type"]
+            .as_str()?
+            .to_owned();
+
+        SseEvent::default()
+            .event(kind)
+            .json_data(&event)
+            .ok()
+            .map(Ok)
+    });
+
+    // Open streams would otherwise hold the graceful shutdown until its
+    // deadline
+    let stream = futures_util::StreamExt::take_until(stream, async move { shutdown.wait().await });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
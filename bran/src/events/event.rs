@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use starduck::Status;
+
+use crate::planner::{OrderKind, ProblemInfo};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventKind {
+    AppRegistered {
+        app: String,
+    },
+    AppUpdated {
+        app: String,
+    },
+    AppRemoved {
+        app: String,
+    },
+    StatusChanged {
+        app: String,
+        from: Option<Status>,
+        to: Status,
+    },
+    DirectivesChanged {
+        app: String,
+    },
+    TargetChanged {
+        app: String,
+    },
+    PlannerCycleStarted,
+    PlannerCycleFinished {
+        duration_ms: i64,
+        orders: usize,
+    },
+    OrderSent {
+        app: String,
+        problem: ProblemInfo,
+        kind: OrderKind,
+    },
+    OrderFailed {
+        app: String,
+        problem: ProblemInfo,
+        kind: OrderKind,
+        error: String,
+    },
+    EscalationExhausted {
+        app: String,
+        problem: ProblemInfo,
+    },
+    CircuitOpened {
+        target: String,
+        /// Applications whose orders go to the target
+        apps: Vec<String>,
+    },
+}
+
+impl EventKind {
+    /// Application the event is about. Planner and circuit events concern
+    /// every application.
+    pub fn app(&self) -> Option<&str> {
+        match self {
+            EventKind::AppRegistered { app }
+            | EventKind::AppUpdated { app }
+            | EventKind::AppRemoved { app }
+            | EventKind::StatusChanged { app, .. }
+            | EventKind::DirectivesChanged { app }
+            | EventKind::TargetChanged { app }
+            | EventKind::OrderSent { app, .. }
+            | EventKind::OrderFailed { app, .. }
+            | EventKind::EscalationExhausted { app, .. } => Some(app),
+            EventKind::PlannerCycleStarted
+            | EventKind::PlannerCycleFinished { .. }
+            | EventKind::CircuitOpened { .. } => None,
+        }
+    }
+
+    /// What changed in `app` between two of its
+    /// [`app_view`](crate::aggregator::ApplicationRegister::app_view)s.
+    pub fn changes(app: &str, before: &Value, after: &Value) -> Vec<Self> {
+        let mut events = Vec::new();
+        let app = app.to_owned();
+
+        match (&before["application"], &after["application"]) {
+            (Value::Null, Value::Null) => {}
+            (Value::Null, _) => events.push(EventKind::AppRegistered { app: app.clone() }),
+            (_, Value::Null) => return vec![EventKind::AppRemoved { app }],
+            (old, new) if old != new => events.push(EventKind::AppUpdated { app: app.clone() }),
+            _ => {}
+        }
+
+        let status =
+            |view: &Value| serde_json::from_value::<Status>(view["status"]["status"].clone());
+        if let (from, Ok(to)) = (status(before).ok(), status(after)) {
+            if from != Some(to) {
+                events.push(EventKind::StatusChanged {
+                    app: app.clone(),
+                    from,
+                    to,
+                });
+            }
+        }
+
+        if before["directives"] != after["directives"] {
+            events.push(EventKind::DirectivesChanged { app: app.clone() });
+        }
+
+        if before["target"] != after["target"] {
+            events.push(EventKind::TargetChanged { app });
+        }
+
+        events
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+impl Event {
+    /// Whether a subscriber that asked for `app` should get this event.
+    pub fn concerns(&self, app: Option<&str>) -> bool {
+        match (app, self.kind.app()) {
+            (Some(wanted), Some(app)) => wanted == app,
+            _ => true,
+        }
+    }
+}
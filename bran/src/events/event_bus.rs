@@ -0,0 +1,53 @@
+use std::collections::BTreeSet;
+
+use chrono::Utc;
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use super::{Event, EventKind};
+use crate::config::EventsConfig;
+
+/// Fans register and planner events out to every stream subscriber.
+/// Subscribers that fall behind lose the oldest events.
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    pub fn new(config: &EventsConfig) -> Self {
+        let (sender, _) = broadcast::channel(config.buffer.max(1));
+
+        Self { sender }
+    }
+
+    pub fn publish(&self, kind: EventKind) {
+        debug!("Publishing event {:?}", &kind);
+
+        // Sending only fails when nobody is listening
+        let _ = self.sender.send(Event {
+            timestamp: Utc::now(),
+            kind,
+        });
+    }
+
+    /// Publishes what changed in every application between two
+    /// [`view`](crate::aggregator::ApplicationRegister::view)s of the
+    /// register, for changes made to several applications at once.
+    pub fn publish_changes(&self, before: &Value, after: &Value) {
+        let apps = [before, after]
+            .into_iter()
+            .filter_map(Value::as_object)
+            .flat_map(|view| view.keys())
+            .collect::<BTreeSet<_>>();
+
+        for app in apps {
+            for kind in EventKind::changes(app, &before[app], &after[app]) {
+                self.publish(kind);
+            }
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::{RawPathParams, Request},
+    http::Method,
+    middleware::Next,
+    response::Response,
+    Extension,
+};
+
+use super::{EventBus, EventKind};
+use crate::aggregator::ApplicationRegister;
+
+const APP_PARAM: &str = "app";
+
+/// Publishes what a successful request changed in the targeted application.
+pub async fn publish_changes(
+    Extension(events): Extension<Arc<EventBus>>,
+    Extension(app_reg): Extension<Arc<Mutex<ApplicationRegister>>>,
+    params: Option<RawPathParams>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let app = params.as_ref().and_then(|p| {
+        p.iter()
+            .find(|(k, _)| *k == APP_PARAM)
+            .map(|(_, v)| v.to_owned())
+    });
+
+    let mutating = matches!(
+        *request.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+
+    let Some(app) = app.filter(|_| mutating) else {
+        return next.run(request).await;
+    };
+
+    let before = app_reg.lock().unwrap().app_view(&app);
+    let response = next.run(request).await;
+
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let after = app_reg.lock().unwrap().app_view(&app);
+
+    for kind in EventKind::changes(&app, &before, &after) {
+        events.publish(kind);
+    }
+
+    response
+}
@@ -0,0 +1,7 @@
+mod event;
+mod event_bus;
+mod middleware;
+
+pub use event::{Event, EventKind};
+pub use event_bus::EventBus;
+pub use middleware::publish_changes;
@@ -0,0 +1,188 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use starduck::Application;
+
+use crate::config::JournalConfig;
+use crate::simulation::StateUpdate;
+
+const FILE_STEM: &str = "journal";
+const FILE_EXTENSION: &str = "jsonl";
+
+/// A request body as bran received it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub at: DateTime<Utc>,
+    pub method: String,
+    pub path: String,
+    pub app: Option<String>,
+    /// Status bran answered with
+    pub status: u16,
+    /// Set when the secrets in the body were replaced, so the request
+    /// can't be sent again as it was
+    #[serde(default)]
+    pub redacted: bool,
+    pub body: Value,
+}
+
+impl JournalEntry {
+    /// The state report this entry holds, if it is one.
+    pub fn state_update(&self) -> Option<StateUpdate> {
+        if self.method != "PUT" {
+            return None;
+        }
+
+        let state = serde_json::from_value::<Application>(self.body.clone()).ok()?;
+
+        Some(StateUpdate {
+            at: self.at,
+            app: self.app.clone()?,
+            state,
+        })
+    }
+}
+
+struct Current {
+    file: File,
+    size: u64,
+}
+
+/// JSON lines files in a directory, rotated by size. `journal.jsonl` is
+/// written to, `journal.1.jsonl` is the one before it and so on.
+pub struct Journal {
+    dir: PathBuf,
+    max_file_bytes: u64,
+    max_files: usize,
+    current: Mutex<Current>,
+}
+
+impl Journal {
+    /// The journal set in `config`, if any.
+    pub fn from_config(config: &JournalConfig) -> Result<Option<Self>> {
+        config
+            .dir
+            .as_ref()
+            .map(|dir| Self::open(dir.clone(), config.max_file_bytes, config.max_files))
+            .transpose()
+    }
+
+    pub fn open(dir: PathBuf, max_file_bytes: u64, max_files: usize) -> Result<Self> {
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Could not create journal directory {}", dir.display()))?;
+
+        let current = open_current(&dir)?;
+
+        info!("Recording request bodies to {}", dir.display());
+
+        Ok(Self {
+            dir,
+            max_file_bytes,
+            max_files: max_files.max(1),
+            current: Mutex::new(current),
+        })
+    }
+
+    pub fn append(&self, entry: &JournalEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let mut current = self.current.lock().unwrap();
+
+        if current.size > 0 && current.size + line.len() as u64 > self.max_file_bytes {
+            *current = self.rotate()?;
+        }
+
+        current.file.write_all(line.as_bytes())?;
+        current.file.flush()?;
+        current.size += line.len() as u64;
+
+        Ok(())
+    }
+
+    /// Shifts every file one place back, dropping the oldest, and opens a
+    /// new file to write to.
+    fn rotate(&self) -> Result<Current> {
+        let _ = fs::remove_file(file_path(&self.dir, self.max_files - 1));
+
+        for n in (0..self.max_files - 1).rev() {
+            let from = file_path(&self.dir, n);
+
+            if from.exists() {
+                fs::rename(&from, file_path(&self.dir, n + 1))
+                    .with_context(|| format!("Could not rotate {}", from.display()))?;
+            }
+        }
+
+        debug!("Rotated the journal in {}", self.dir.display());
+
+        open_current(&self.dir)
+    }
+}
+
+fn file_path(dir: &Path, n: usize) -> PathBuf {
+    match n {
+        0 => dir.join(format!("{FILE_STEM}.{FILE_EXTENSION}")),
+        n => dir.join(format!("{FILE_STEM}.{n}.{FILE_EXTENSION}")),
+    }
+}
+
+fn open_current(dir: &Path) -> Result<Current> {
+    let path = file_path(dir, 0);
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Could not open journal {}", path.display()))?;
+    let size = file.metadata()?.len();
+
+    Ok(Current { file, size })
+}
+
+/// Entries of a journal file, or of every file of a journal directory,
+/// oldest first. Malformed lines, like one cut short by a crash, are
+/// skipped.
+pub fn read_journal(path: &Path) -> Result<Vec<JournalEntry>> {
+    let files = if path.is_dir() {
+        let mut files = (0..)
+            .map(|n| file_path(path, n))
+            .take_while(|file| file.exists())
+            .collect::<Vec<_>>();
+        files.reverse();
+        files
+    } else {
+        vec![path.to_owned()]
+    };
+
+    let mut entries = Vec::new();
+
+    for file in files {
+        let reader = File::open(&file)
+            .with_context(|| format!("Could not read journal {}", file.display()))?;
+
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<JournalEntry>(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warn!(
+                    "Skipping malformed entry on line {} of {}: {e}",
+                    i + 1,
+                    file.display()
+                ),
+            }
+        }
+    }
+
+    Ok(entries)
+}
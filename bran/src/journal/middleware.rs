@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{MatchedPath, OriginalUri, RawPathParams, Request},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+
+use super::{Journal, JournalEntry};
+use crate::aggregator::{DothingTarget, Webhook};
+
+const APP_PARAM: &str = "app";
+/// Same as the limit axum puts on the JSON bodies
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+const TARGET_ROUTE: &str = "/directives/target/";
+const WEBHOOK_ROUTE: &str = "/directives/webhook/";
+
+/// Records the body of every request that can change the register in the
+/// journal, with the status it got. Does nothing when there is no journal.
+pub async fn record_bodies(
+    journal: Option<Extension<Arc<Journal>>>,
+    matched: Option<MatchedPath>,
+    params: Option<RawPathParams>,
+    OriginalUri(uri): OriginalUri,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(Extension(journal)) = journal else {
+        return next.run(request).await;
+    };
+
+    if !matches!(
+        *request.method(),
+        Method::POST | Method::PUT | Method::PATCH
+    ) {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+
+    // The body is read here, the handler gets a copy
+    let bytes = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let msg = format!("Could not read the request body: {e}");
+            error!("{}", msg);
+            return (StatusCode::BAD_REQUEST, Json(json!({"msg": msg}))).into_response();
+        }
+    };
+
+    let app = params.as_ref().and_then(|p| {
+        p.iter()
+            .find(|(k, _)| *k == APP_PARAM)
+            .map(|(_, v)| v.to_owned())
+    });
+
+    let body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(body) => body,
+        Err(_) if bytes.is_empty() => Value::Null,
+        Err(_) => Value::String(String::from_utf8_lossy(&bytes).into_owned()),
+    };
+
+    // Secrets have no place in the journal. Bodies that don't parse are
+    // refused by the handler anyway, so they are left out
+    let route = matched.as_ref().map_or("", |m| m.as_str());
+    let (body, redacted) = match route {
+        r if r.starts_with(TARGET_ROUTE) => {
+            let target = serde_json::from_value::<DothingTarget>(body).ok();
+            (json!(target.map(|t| t.redacted())), true)
+        }
+        r if r.starts_with(WEBHOOK_ROUTE) => {
+            let webhook = serde_json::from_value::<Webhook>(body).ok();
+            (json!(webhook.map(|w| w.redacted())), true)
+        }
+        _ => (body, false),
+    };
+
+    let at = Utc::now();
+    let method = parts.method.to_string();
+    // Nested routers only see the rest of the path
+    let path = uri.path().to_owned();
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+
+    let entry = JournalEntry {
+        at,
+        method,
+        path,
+        app,
+        status: response.status().as_u16(),
+        redacted,
+        body,
+    };
+
+    if let Err(e) = journal.append(&entry) {
+        error!("Could not write journal entry: {e}");
+    }
+
+    response
+}
@@ -0,0 +1,6 @@
+#[allow(clippy::module_inception)]
+mod journal;
+mod middleware;
+
+pub use journal::{read_journal, Journal, JournalEntry};
+pub use middleware::record_bodies;
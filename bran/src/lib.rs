@@ -0,0 +1,21 @@
+#[macro_use]
+extern crate tracing;
+
+pub mod aggregator;
+pub mod audit;
+pub mod auth;
+pub mod cli;
+pub mod config;
+pub mod endpoints;
+pub mod events;
+pub mod journal;
+pub mod manifests;
+pub mod metrics;
+pub mod notifier;
+pub mod planner;
+pub mod shutdown;
+pub mod simulation;
+pub mod telemetry;
+pub mod tls;
+
+pub use aggregator::ApplicationRegister;
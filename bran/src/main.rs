@@ -1,40 +1,248 @@
-mod aggregator;
-mod endpoints;
-mod planner;
-
 #[macro_use]
-extern crate log;
+extern crate tracing;
 
 use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use axum::{Extension, Router};
-use planner::Planner;
+use clap::Parser;
 use tokio::net::TcpListener;
+use tokio::sync::watch;
 
-use aggregator::ApplicationRegister;
-
-use starduck::utils::PORT;
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use bran::aggregator::{ApplicationRegister, ImportMode};
+use bran::audit::AuditLog;
+use bran::auth::Authenticator;
+use bran::cli::{self, Cli, Command};
+use bran::config::{self, Config, ConfigArgs, Reloader};
+use bran::endpoints::{self, Services};
+use bran::events::EventBus;
+use bran::journal::Journal;
+use bran::manifests::ManifestSync;
+use bran::metrics::Metrics;
+use bran::notifier::Notifier;
+use bran::planner::{Planner, PlannerState};
+use bran::shutdown::Shutdown;
+use bran::telemetry::{self, Telemetry};
+use bran::tls::{self, TlsSettings};
 
 #[tokio::main]
 async fn main() {
-    env_logger::init();
+    let Cli {
+        config: args,
+        print_config,
+        command,
+    } = Cli::parse();
+
+    let config = config::load(&args).unwrap_or_else(|e| {
+        eprintln!("{e:#}");
+        std::process::exit(-1);
+    });
+
+    if print_config {
+        match toml::to_string_pretty(&config.redacted()) {
+            Ok(rendered) => print!("{rendered}"),
+            Err(e) => eprintln!("Could not print the config: {e}"),
+        }
+        return;
+    }
+
+    let command = match command.unwrap_or_default() {
+        Command::Serve => return serve(args, config).await,
+        command => command,
+    };
+
+    if let Err(e) = telemetry::init_stderr(&config.logging.level) {
+        eprintln!("Could not set up logging: {e}");
+    }
+
+    let result = match command {
+        Command::Serve => unreachable!("served above"),
+        Command::Plan { state } => cli::plan(&config, &state),
+        Command::Simulate {
+            state,
+            updates,
+            until,
+        } => cli::simulate(&config, &state, &updates, until).await,
+        Command::Replay {
+            journal,
+            simulate: Some(state),
+            until,
+            ..
+        } => cli::replay_simulated(&config, &journal, &state, until).await,
+        Command::Replay {
+            journal,
+            url: Some(url),
+            token,
+            speed,
+            ..
+        } => cli::replay(&journal, &url, token.as_deref(), speed).await,
+        Command::Replay { .. } => Err(anyhow::anyhow!("Replay needs --url or --simulate")),
+        Command::Validate { file, kind, app } => {
+            cli::validate(&config, &file, kind, app.as_deref())
+        }
+        Command::Export { output } => cli::export(&config.register, output.as_deref()),
+        Command::Import { file, replace } => {
+            let mode = if replace {
+                ImportMode::Replace
+            } else {
+                ImportMode::Merge
+            };
+            cli::import(&config.register, &file, mode)
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("{e:#}");
+        std::process::exit(-1);
+    }
+}
+
+/// Runs the API and the planner until shutdown.
+async fn serve(args: ConfigArgs, config: Config) {
+    let telemetry = Telemetry::init(&config.logging).unwrap_or_else(|e| {
+        eprintln!("Could not set up logging: {e}");
+        std::process::exit(-1);
+    });
+
+    // Stops the server and the planner on SIGTERM or SIGINT
+    let shutdown = Shutdown::new();
+    tokio::spawn(shutdown.clone().on_signal());
+
+    // Settings that can change without a restart
+    let (planner_config, planner_updates) = watch::channel(config.planner.clone());
+    let reloader = Reloader::new(
+        args.clone(),
+        config.clone(),
+        planner_config,
+        telemetry.log_level(),
+    );
+    tokio::spawn(reloader.run(config::config_file(&args)));
 
     // Locate the space to handle the objective apps
-    let app_aggregator = ApplicationRegister::new();
+    let app_aggregator = ApplicationRegister::restore(&config.register).unwrap_or_else(|e| {
+        error!("Could not restore the register: {e:#}");
+        std::process::exit(-1);
+    });
     let state_axum = Arc::new(Mutex::new(app_aggregator));
     let state_planner = Arc::clone(&state_axum);
 
-    tokio::spawn(async move {
-        let port = starduck::utils::get(PORT).unwrap_or(8014);
+    // Planner bookkeeping, readable from the endpoints
+    let planner_state_file = config.register.planner_state_file();
+    let planner_state = PlannerState::restore(planner_state_file.as_deref()).unwrap_or_else(|e| {
+        error!("Could not restore the planner state: {e:#}");
+        std::process::exit(-1);
+    });
+    let planner_state = Arc::new(Mutex::new(planner_state));
+    let planner_state_axum = Arc::clone(&planner_state);
+    let planner_state_saved = Arc::clone(&planner_state);
+
+    // Register and planner events, streamed to the clients
+    let events = Arc::new(EventBus::new(&config.events));
+    let events_axum = Arc::clone(&events);
+
+    // Directives and objectives kept in files, read-only through the API
+    if let Some(dir) = &config.register.manifests_dir {
+        let manifest_sync = ManifestSync::new(Arc::clone(&state_axum), dir.clone())
+            .with_events(Arc::clone(&events));
+        manifest_sync.sync();
+        tokio::spawn(manifest_sync.run());
+    }
+
+    let metrics = Arc::new(Metrics::new().unwrap_or_else(|e| {
+        error!("Could not set up metrics: {e}");
+        std::process::exit(-1);
+    }));
+    let metrics_axum = Arc::clone(&metrics);
+
+    // Alerts for the application webhooks
+    let notifier = Notifier::new(Arc::clone(&state_axum), &config.webhooks);
+    tokio::spawn(notifier.run(Arc::clone(&events)));
+
+    let authenticator = Authenticator::from_config(&config.auth).unwrap_or_else(|e| {
+        error!("Could not load API tokens: {e}");
+        std::process::exit(-1);
+    });
+
+    let audit_log = AuditLog::from_config(&config.audit).unwrap_or_else(|e| {
+        error!("Could not open the audit log: {e}");
+        std::process::exit(-1);
+    });
+
+    let journal = Journal::from_config(&config.journal).unwrap_or_else(|e| {
+        error!("Could not open the journal: {e:#}");
+        std::process::exit(-1);
+    });
+
+    let tls_settings = TlsSettings::from_config(&config.server.tls).unwrap_or_else(|e| {
+        error!("Invalid TLS settings: {e}");
+        std::process::exit(-1);
+    });
+
+    let server_config = config.server.clone();
+    let deadline = Duration::from_secs(server_config.shutdown_timeout_secs);
+    let state_saved = Arc::clone(&state_axum);
+    let shutdown_axum = shutdown.clone();
 
-        let app = Router::new()
-            .nest("/", endpoints::extras_router())
-            .nest("/apps", endpoints::main_router())
-            .nest("/directives", endpoints::directives_router())
-            .layer(Extension(state_axum));
+    let server = tokio::spawn(async move {
+        let port = server_config.port;
+
+        let app = endpoints::router(Services {
+            register: state_axum,
+            planner_state: planner_state_axum,
+            events: events_axum,
+            metrics: metrics_axum,
+            authenticator: Arc::new(authenticator),
+            audit_log: Arc::new(audit_log),
+            journal: journal.map(Arc::new),
+            shutdown: shutdown_axum.clone(),
+        });
 
         let addr = SocketAddr::from(([0, 0, 0, 0], port));
+
+        if let Some(settings) = tls_settings {
+            let server_config = settings.server_config().unwrap_or_else(|e| {
+                error!("Could not load TLS certificates: {e}");
+                std::process::exit(-1);
+            });
+
+            let rustls_config = RustlsConfig::from_config(Arc::new(server_config));
+            tokio::spawn(tls::reload_on_sighup(
+                rustls_config.clone(),
+                settings.clone(),
+            ));
+
+            info!(
+                "Initializing server at {} with TLS{}",
+                &addr,
+                if settings.client_ca.is_some() {
+                    " and client certificates"
+                } else {
+                    ""
+                }
+            );
+
+            // Stop accepting connections and give the open ones until the
+            // deadline
+            let handle = Handle::new();
+            let stopper = handle.clone();
+            tokio::spawn(async move {
+                shutdown_axum.wait().await;
+                stopper.graceful_shutdown(Some(deadline));
+            });
+
+            axum_server::bind_rustls(addr, rustls_config)
+                .handle(handle)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+                .unwrap_or_else(|e| {
+                    error!("Could not start server: {e}");
+                    std::process::exit(-1);
+                });
+
+            return;
+        }
+
         let tcp_listener = TcpListener::bind(&addr).await.unwrap_or_else(|e| {
             error!("Could not start server: {e}");
             std::process::exit(-1);
@@ -46,6 +254,7 @@ async fn main() {
             tcp_listener,
             app.into_make_service_with_connect_info::<SocketAddr>(),
         )
+        .with_graceful_shutdown(async move { shutdown_axum.wait().await })
         .await
         .unwrap_or_else(|e| {
             error!("Could not start server: {e}");
@@ -53,9 +262,56 @@ async fn main() {
         });
     });
 
-    let mut planner = Planner::new(state_planner);
+    let mut planner = Planner::new(
+        state_planner,
+        planner_state,
+        events,
+        metrics,
+        planner_updates,
+        shutdown.clone(),
+    );
+
+    let mut planner = tokio::spawn(async move { planner.watch_over().await });
 
-    loop {
-        planner.watch_over().await;
+    shutdown.wait().await;
+    info!("Shutting down, waiting up to {}s", deadline.as_secs());
+
+    let drained = tokio::time::timeout(deadline, async {
+        let _ = server.await;
+        let _ = (&mut planner).await;
+    })
+    .await;
+
+    // The state is only saved once no cycle can change it anymore
+    if drained.is_err() {
+        warn!("Shutdown deadline passed, in-flight work is dropped");
+        planner.abort();
+        let _ = planner.await;
     }
+
+    if let Some(path) = &config.register.state_file {
+        let saved = match state_saved.lock() {
+            Ok(register) => register.save(path),
+            Err(_) => Err(anyhow::anyhow!("register lock poisoned")),
+        };
+
+        match saved {
+            Ok(()) => info!("Saved the register to {}", path.display()),
+            Err(e) => error!("Could not save the register: {e:#}"),
+        }
+    }
+
+    if let Some(path) = &planner_state_file {
+        let saved = match planner_state_saved.lock() {
+            Ok(state) => state.save(path),
+            Err(_) => Err(anyhow::anyhow!("planner state lock poisoned")),
+        };
+
+        match saved {
+            Ok(()) => info!("Saved the planner state to {}", path.display()),
+            Err(e) => error!("Could not save the planner state: {e:#}"),
+        }
+    }
+
+    info!("Shutdown complete");
 }
@@ -0,0 +1,105 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use starduck::{Application, Directives};
+
+type AppName = String;
+type LocationKey = String;
+
+/// Directives of one application, and optionally its objective, as kept
+/// in a file of the manifests directory.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub app: AppName,
+    #[serde(default)]
+    pub application: Option<Application>,
+    #[serde(default)]
+    pub directives: HashMap<LocationKey, Directives>,
+}
+
+impl Manifest {
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Could not read manifest {}", path.display()))?;
+
+        let manifest: Self = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => serde_yaml::from_str(&content)?,
+            _ => serde_json::from_str(&content)?,
+        };
+
+        if let Some(application) = &manifest.application {
+            for location in manifest.directives.keys() {
+                if application.locations.get(location).is_none() {
+                    bail!(
+                        "Directives for location {} which is not in {}",
+                        location,
+                        manifest.app
+                    );
+                }
+            }
+        }
+
+        Ok(manifest)
+    }
+}
+
+fn is_manifest(path: &Path) -> bool {
+    path.is_file()
+        && matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("yaml" | "yml" | "json")
+        )
+}
+
+/// What a manifests directory holds.
+#[derive(Debug, Default)]
+pub struct ManifestDir {
+    /// The valid manifests, by application
+    pub manifests: BTreeMap<AppName, (PathBuf, Manifest)>,
+    /// Files that are there but could not be loaded
+    pub invalid: BTreeSet<PathBuf>,
+}
+
+/// The manifests in `dir`. Invalid files are left out, so one bad file
+/// doesn't take the others down with it.
+pub fn read_dir(dir: &Path) -> Result<ManifestDir> {
+    let mut paths = fs::read_dir(dir)
+        .with_context(|| format!("Could not read manifests directory {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_manifest(path))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut read = ManifestDir::default();
+
+    for path in paths {
+        let manifest = match Manifest::read(&path) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                error!("Skipping manifest {}: {e:#}", path.display());
+                read.invalid.insert(path);
+                continue;
+            }
+        };
+
+        if let Some((first, _)) = read.manifests.get(&manifest.app) {
+            error!(
+                "Skipping manifest {}: {} is already declared in {}",
+                path.display(),
+                manifest.app,
+                first.display()
+            );
+            read.invalid.insert(path);
+            continue;
+        }
+
+        read.manifests
+            .insert(manifest.app.clone(), (path, manifest));
+    }
+
+    Ok(read)
+}
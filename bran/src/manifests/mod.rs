@@ -0,0 +1,5 @@
+mod manifest;
+mod sync;
+
+pub use manifest::{read_dir, Manifest, ManifestDir};
+pub use sync::ManifestSync;
@@ -0,0 +1,185 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
+
+use super::{read_dir, ManifestDir};
+use crate::aggregator::{ApplicationRegister, Managed};
+use crate::events::EventBus;
+
+/// Checkouts and editors touch several files in a row.
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// Keeps the register in line with the manifests directory. What the
+/// files declare can only be changed by changing the files.
+pub struct ManifestSync {
+    register: Arc<Mutex<ApplicationRegister>>,
+    dir: PathBuf,
+    events: Option<Arc<EventBus>>,
+}
+
+impl ManifestSync {
+    pub fn new(register: Arc<Mutex<ApplicationRegister>>, dir: PathBuf) -> Self {
+        Self {
+            register,
+            dir,
+            events: None,
+        }
+    }
+
+    /// Publishes what each sync changes to `events`.
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Loads the manifests into the register. Applications whose manifest
+    /// is gone lose what it declared, the ones whose manifest can't be
+    /// loaded keep what it declared last.
+    pub fn sync(&self) {
+        let read = match read_dir(&self.dir) {
+            Ok(read) => read,
+            Err(e) => {
+                error!("Manifests not synced, keeping the loaded ones: {e:#}");
+                return;
+            }
+        };
+
+        let mut register = self.register.lock().unwrap();
+        let before = register.view();
+
+        apply(&mut register, read);
+
+        if let Some(events) = &self.events {
+            events.publish_changes(&before, &register.view());
+        }
+    }
+
+    /// Syncs again on SIGHUP and whenever a file in the directory changes.
+    pub async fn run(self) {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(k) => Some(k),
+            Err(e) => {
+                error!("Could not listen for SIGHUP, manifests won't be synced on it: {e}");
+                None
+            }
+        };
+
+        let (tx, mut changed) = mpsc::channel(1);
+
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if event.is_ok_and(|e| !e.kind.is_access()) {
+                let _ = tx.try_send(());
+            }
+        });
+
+        let _watcher = match watcher
+            .and_then(|mut w| w.watch(&self.dir, RecursiveMode::NonRecursive).map(|_| w))
+        {
+            Ok(w) => {
+                info!("Watching {} for manifest changes", self.dir.display());
+                Some(w)
+            }
+            Err(e) => {
+                error!("Could not watch {}: {e}", self.dir.display());
+                None
+            }
+        };
+
+        loop {
+            tokio::select! {
+                Some(_) = async { hangup.as_mut()?.recv().await } => {
+                    info!("SIGHUP received. Syncing manifests");
+                }
+                Some(_) = changed.recv() => {
+                    tokio::time::sleep(SETTLE_TIME).await;
+                    while changed.try_recv().is_ok() {}
+
+                    info!("Manifests changed. Syncing");
+                }
+                else => return,
+            }
+
+            self.sync();
+        }
+    }
+}
+
+fn apply(register: &mut ApplicationRegister, read: ManifestDir) {
+    let ManifestDir { manifests, invalid } = read;
+
+    let mut gone = Vec::new();
+
+    for (app, managed) in &register.managed {
+        if manifests.contains_key(app) {
+            continue;
+        }
+
+        if invalid.contains(&managed.source) {
+            warn!(
+                "Keeping {} as last loaded, its manifest {} is invalid",
+                app,
+                managed.source.display()
+            );
+            continue;
+        }
+
+        gone.push(app.clone());
+    }
+
+    for app in gone {
+        let Some(managed) = register.managed.remove(&app) else {
+            continue;
+        };
+
+        if managed.spec {
+            register.remove_app(&app);
+            info!(
+                "Removed {}, its manifest {} is gone",
+                app,
+                managed.source.display()
+            );
+        } else {
+            register.directives.remove(&app);
+            register.record_version(&app);
+            info!(
+                "Removed the directives of {}, their manifest {} is gone",
+                app,
+                managed.source.display()
+            );
+        }
+    }
+
+    for (app, (source, manifest)) in manifests {
+        let before = register.app_view(&app);
+        let managed = Managed {
+            source,
+            spec: manifest.application.is_some(),
+        };
+
+        if let Some(application) = manifest.application {
+            register.specs.insert(app.clone(), application);
+        } else if !register.specs.contains_key(&app) {
+            warn!(
+                "{} declares directives for {}, which has no objective yet",
+                managed.source.display(),
+                app
+            );
+        }
+
+        register.directives.insert(app.clone(), manifest.directives);
+
+        let changed = register.app_view(&app) != before;
+        if changed {
+            register.record_version(&app);
+        }
+        if changed || register.managed.get(&app) != Some(&managed) {
+            info!("Synced {} from {}", app, managed.source.display());
+        }
+
+        register.managed.insert(app, managed);
+    }
+}
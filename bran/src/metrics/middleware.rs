@@ -0,0 +1,39 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+    Extension,
+};
+
+use super::Metrics;
+
+/// Counts and times every request by the route it matched.
+pub async fn track_requests(
+    Extension(metrics): Extension<Arc<Metrics>>,
+    matched: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let route = matched
+        .as_ref()
+        .map(|m| m.as_str().to_owned())
+        .unwrap_or_default();
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    metrics
+        .http_latency
+        .with_label_values(&[&method, &route])
+        .observe(started.elapsed().as_secs_f64());
+    metrics
+        .http_requests
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .inc();
+
+    response
+}
@@ -0,0 +1,5 @@
+mod middleware;
+mod registry;
+
+pub use middleware::track_requests;
+pub use registry::Metrics;
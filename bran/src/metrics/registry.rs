@@ -0,0 +1,141 @@
+use anyhow::Result;
+use prometheus::{
+    Encoder, Gauge, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec,
+    Opts, Registry, TextEncoder,
+};
+use starduck::{Location, Status};
+
+use crate::aggregator::ApplicationRegister;
+
+const STATUSES: [Status; 5] = [
+    Status::Uninitialized,
+    Status::Coherent,
+    Status::Degraded,
+    Status::Critical,
+    Status::Fault,
+];
+
+/// Prometheus series exported on `/metrics`.
+pub struct Metrics {
+    registry: Registry,
+    apps: IntGaugeVec,
+    non_coherent: IntGauge,
+    pub cycle_duration: Histogram,
+    pub last_cycle: Gauge,
+    pub orders: IntCounterVec,
+    pub dothing_latency: HistogramVec,
+    pub http_requests: IntCounterVec,
+    pub http_latency: HistogramVec,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self> {
+        let apps = IntGaugeVec::new(
+            Opts::new("bran_apps", "Registered applications by reported status"),
+            &["status"],
+        )?;
+        let non_coherent = IntGauge::new(
+            "bran_non_coherent_data_requirements",
+            "Data requirements that are not coherent",
+        )?;
+        let cycle_duration = Histogram::with_opts(HistogramOpts::new(
+            "bran_planner_cycle_duration_seconds",
+            "Time spent on each planner cycle",
+        ))?;
+        let last_cycle = Gauge::new(
+            "bran_planner_last_cycle_timestamp_seconds",
+            "Unix time at which the last planner cycle finished",
+        )?;
+        let orders = IntCounterVec::new(
+            Opts::new("bran_orders_total", "Orders sent to dothing"),
+            &["kind", "outcome"],
+        )?;
+        let dothing_latency = HistogramVec::new(
+            HistogramOpts::new(
+                "bran_dothing_request_duration_seconds",
+                "Latency of the requests to dothing",
+            ),
+            &["kind"],
+        )?;
+        let http_requests = IntCounterVec::new(
+            Opts::new("bran_http_requests_total", "HTTP requests served"),
+            &["method", "route", "status"],
+        )?;
+        let http_latency = HistogramVec::new(
+            HistogramOpts::new(
+                "bran_http_request_duration_seconds",
+                "Latency of the HTTP requests served",
+            ),
+            &["method", "route"],
+        )?;
+
+        let registry = Registry::new();
+        registry.register(Box::new(apps.clone()))?;
+        registry.register(Box::new(non_coherent.clone()))?;
+        registry.register(Box::new(cycle_duration.clone()))?;
+        registry.register(Box::new(last_cycle.clone()))?;
+        registry.register(Box::new(orders.clone()))?;
+        registry.register(Box::new(dothing_latency.clone()))?;
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_latency.clone()))?;
+
+        Ok(Self {
+            registry,
+            apps,
+            non_coherent,
+            cycle_duration,
+            last_cycle,
+            orders,
+            dothing_latency,
+            http_requests,
+            http_latency,
+        })
+    }
+
+    /// Refreshes the register gauges and renders every series in the text
+    /// format.
+    pub fn render(&self, register: &ApplicationRegister) -> Result<String> {
+        for status in STATUSES {
+            let count = register
+                .specs
+                .keys()
+                .filter(|app| {
+                    register
+                        .statuses
+                        .get(*app)
+                        .map_or(Status::Uninitialized, |s| s.status)
+                        == status
+                })
+                .count();
+
+            self.apps
+                .with_label_values(&[&format!("{:?}", status)])
+                .set(count as i64);
+        }
+
+        let non_coherent = register
+            .statuses
+            .values()
+            .map(|app| non_coherent_count(&app.locations))
+            .sum::<usize>();
+        self.non_coherent.set(non_coherent as i64);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+fn non_coherent_count(location: &Location) -> usize {
+    location
+        .data_requirements
+        .values()
+        .filter(|data_req| data_req.status != Status::Coherent)
+        .count()
+        + location
+            .locations
+            .values()
+            .map(non_coherent_count)
+            .sum::<usize>()
+}
@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use starduck::Status;
+
+use crate::events::EventKind;
+use crate::planner::ProblemInfo;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "alert", rename_all = "snake_case")]
+pub enum AlertKind {
+    /// The last remediation step didn't fix the problem
+    EscalationExhausted {
+        problem: ProblemInfo,
+    },
+    DothingUnreachable {
+        target: String,
+    },
+    AppNonCoherent {
+        status: Status,
+    },
+    AppRecovered,
+}
+
+/// Payload posted to the webhook of an application.
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub timestamp: DateTime<Utc>,
+    pub app: String,
+    #[serde(flatten)]
+    pub kind: AlertKind,
+}
+
+impl Alert {
+    /// Alerts raised by an event, one per application it concerns.
+    pub fn from_event(event: &EventKind) -> Vec<Alert> {
+        let alert = |app: &str, kind: AlertKind| Alert {
+            timestamp: Utc::now(),
+            app: app.to_owned(),
+            kind,
+        };
+
+        match event {
+            EventKind::EscalationExhausted { app, problem } => vec![alert(
+                app,
+                AlertKind::EscalationExhausted {
+                    problem: problem.clone(),
+                },
+            )],
+            EventKind::CircuitOpened { target, apps } => apps
+                .iter()
+                .map(|app| {
+                    alert(
+                        app,
+                        AlertKind::DothingUnreachable {
+                            target: target.clone(),
+                        },
+                    )
+                })
+                .collect(),
+            EventKind::StatusChanged { app, from, to } => {
+                let was_faulty = from.is_some_and(is_faulty);
+
+                if is_faulty(*to) && !was_faulty {
+                    vec![alert(app, AlertKind::AppNonCoherent { status: *to })]
+                } else if *to == Status::Coherent && was_faulty {
+                    vec![alert(app, AlertKind::AppRecovered)]
+                } else {
+                    Vec::new()
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn is_faulty(status: Status) -> bool {
+    matches!(status, Status::Degraded | Status::Critical | Status::Fault)
+}
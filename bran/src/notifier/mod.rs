@@ -0,0 +1,6 @@
+mod alert;
+#[allow(clippy::module_inception)]
+mod notifier;
+
+pub use alert::Alert;
+pub use notifier::Notifier;
@@ -0,0 +1,92 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::broadcast::error::RecvError;
+
+use super::Alert;
+use crate::aggregator::{ApplicationRegister, Webhook};
+use crate::config::WebhooksConfig;
+use crate::events::EventBus;
+
+/// Turns register and planner events into alerts for the application
+/// webhooks.
+pub struct Notifier {
+    register: Arc<Mutex<ApplicationRegister>>,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl Notifier {
+    pub fn new(register: Arc<Mutex<ApplicationRegister>>, config: &WebhooksConfig) -> Self {
+        Self {
+            register,
+            retries: config.retries,
+            backoff: Duration::from_secs(config.backoff_secs),
+        }
+    }
+
+    pub async fn run(self, events: Arc<EventBus>) {
+        let mut receiver = events.subscribe();
+
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Notifier fell behind and skipped {} events", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+
+            for alert in Alert::from_event(&event.kind) {
+                let webhook = self
+                    .register
+                    .lock()
+                    .unwrap()
+                    .webhooks
+                    .get(&alert.app)
+                    .cloned();
+
+                if let Some(webhook) = webhook {
+                    tokio::spawn(deliver(webhook, alert, self.retries, self.backoff));
+                }
+            }
+        }
+    }
+}
+
+/// Posts the alert, doubling the wait between attempts.
+async fn deliver(webhook: Webhook, alert: Alert, retries: u32, backoff: Duration) {
+    let body = match serde_json::to_vec(&alert) {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Could not serialize alert for {}: {e}", &alert.app);
+            return;
+        }
+    };
+
+    let mut wait = backoff;
+
+    for attempt in 0..=retries {
+        match webhook.deliver(&body).await {
+            Ok(_) => {
+                info!("Delivered {:?} alert for {}", &alert.kind, &alert.app);
+                return;
+            }
+            Err(e) if attempt < retries => {
+                warn!(
+                    "Webhook for {} failed ({e}). Retrying in {}s",
+                    &alert.app,
+                    wait.as_secs()
+                );
+                tokio::time::sleep(wait).await;
+                wait *= 2;
+            }
+            Err(e) => error!(
+                "Giving up on webhook for {} after {} attempts: {e}",
+                &alert.app,
+                retries + 1
+            ),
+        }
+    }
+}
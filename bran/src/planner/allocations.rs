@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use starduck::{AdditionOrder, Application, Location};
+use uuid::Uuid;
+
+use super::planner::ProblemInfo;
+
+type AppName = String;
+
+const ROOT_LOCATION: &str = "root";
+const RETENTION_HOURS: i64 = 24;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AllocationStatus {
+    Pending,
+    Reconciled,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Allocation {
+    pub device_uuid: Uuid,
+    pub problem: ProblemInfo,
+    pub order: AdditionOrder,
+    pub allocated_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub status: AllocationStatus,
+}
+
+impl Allocation {
+    fn matches(&self, problem: &ProblemInfo) -> bool {
+        self.problem.location_key == problem.location_key
+            && self.problem.data_requirement_key == problem.data_requirement_key
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Allocations {
+    namespace: Uuid,
+    sequence: u64,
+    entries: HashMap<AppName, Vec<Allocation>>,
+}
+
+impl Allocations {
+    pub fn new(namespace: Uuid) -> Self {
+        Self {
+            namespace,
+            sequence: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Derives the next device UUID for `problem`. Given the same namespace,
+    /// the sequence of UUIDs handed out is always the same.
+    pub fn allocate(&mut self, app_name: &str, problem: &ProblemInfo) -> Uuid {
+        let uuid = self.next_uuid(app_name, problem);
+        self.sequence += 1;
+
+        uuid
+    }
+
+    /// The UUID `allocate` would hand out next, without handing it out.
+    pub fn next_uuid(&self, app_name: &str, problem: &ProblemInfo) -> Uuid {
+        let name = format!(
+            "{}/{}/{}/{}",
+            app_name,
+            problem.location_key,
+            problem.data_requirement_key,
+            self.sequence + 1
+        );
+
+        Uuid::new_v5(&self.namespace, name.as_bytes())
+    }
+
+    pub fn record(
+        &mut self,
+        app_name: &str,
+        problem: &ProblemInfo,
+        order: &AdditionOrder,
+        now: DateTime<Utc>,
+    ) {
+        let Some(device_uuid) = problem.device_uuid else {
+            warn!(
+                "Tried to record an addition without a device uuid: {:?}",
+                problem
+            );
+            return;
+        };
+
+        let allocation = Allocation {
+            device_uuid,
+            problem: problem.clone(),
+            order: order.clone(),
+            allocated_at: now,
+            resolved_at: None,
+            status: AllocationStatus::Pending,
+        };
+
+        self.entries
+            .entry(app_name.to_owned())
+            .or_default()
+            .push(allocation);
+    }
+
+    /// Checks the pending allocations of `app` against its reported components.
+    /// Allocations that showed up are reconciled, the ones older than `timeout`
+    /// are flagged as failed deployments. Returns the newly failed allocations.
+    pub fn reconcile(
+        &mut self,
+        app: &Application,
+        timeout: Duration,
+        now: DateTime<Utc>,
+    ) -> Vec<Allocation> {
+        let Some(allocations) = self.entries.get_mut(&app.name) else {
+            return Vec::new();
+        };
+
+        let mut failed = Vec::new();
+
+        // Reconciled allocations are only kept around for traceability
+        allocations.retain(|a| {
+            a.status != AllocationStatus::Reconciled
+                || a.resolved_at
+                    .is_none_or(|t| now - t < Duration::hours(RETENTION_HOURS))
+        });
+
+        for allocation in allocations
+            .iter_mut()
+            .filter(|a| a.status == AllocationStatus::Pending)
+        {
+            if is_reported(&app.locations, allocation) {
+                info!(
+                    "Device {} reconciled for {} in {}",
+                    allocation.device_uuid,
+                    allocation.problem.data_requirement_key,
+                    allocation.problem.location_key
+                );
+
+                allocation.status = AllocationStatus::Reconciled;
+                allocation.resolved_at = Some(now);
+                continue;
+            }
+
+            if now - allocation.allocated_at > timeout {
+                error!(
+                    "Device {} for {} in {} never appeared. Flagging as failed deployment",
+                    allocation.device_uuid,
+                    allocation.problem.data_requirement_key,
+                    allocation.problem.location_key
+                );
+
+                allocation.status = AllocationStatus::Failed;
+                allocation.resolved_at = Some(now);
+                failed.push(allocation.clone());
+            }
+        }
+
+        failed
+    }
+
+    /// How many allocations across every application are in `status`.
+    pub fn count(&self, status: AllocationStatus) -> usize {
+        self.entries
+            .values()
+            .flatten()
+            .filter(|a| a.status == status)
+            .count()
+    }
+
+    pub fn has_failed(&self, app_name: &str, problem: &ProblemInfo) -> bool {
+        self.entries.get(app_name).is_some_and(|allocations| {
+            allocations
+                .iter()
+                .any(|a| a.status == AllocationStatus::Failed && a.matches(problem))
+        })
+    }
+
+    /// Number of additions for the same location and data requirement as
+    /// `problem` that were sent but haven't been reconciled or timed out yet.
+    pub fn pending_count(&self, app_name: &str, problem: &ProblemInfo) -> usize {
+        self.entries.get(app_name).map_or(0, |allocations| {
+            allocations
+                .iter()
+                .filter(|a| a.status == AllocationStatus::Pending && a.matches(problem))
+                .count()
+        })
+    }
+
+    pub fn get(&self, app_name: &str) -> Option<&Vec<Allocation>> {
+        self.entries.get(app_name)
+    }
+
+    /// Drops a failed allocation so the planner can try the addition again.
+    pub fn clear_failed(&mut self, app_name: &str, device_uuid: &Uuid) -> bool {
+        let Some(allocations) = self.entries.get_mut(app_name) else {
+            return false;
+        };
+
+        let before = allocations.len();
+        allocations
+            .retain(|a| !(a.status == AllocationStatus::Failed && &a.device_uuid == device_uuid));
+
+        before != allocations.len()
+    }
+}
+
+fn is_reported(root: &Location, allocation: &Allocation) -> bool {
+    let location = match allocation.problem.location_key.as_str() {
+        ROOT_LOCATION => Some(root),
+        key => root.get(key),
+    };
+
+    location
+        .and_then(|l| {
+            l.data_requirements
+                .get(&allocation.problem.data_requirement_key)
+        })
+        .is_some_and(|d| d.get_component_by_uuid(allocation.device_uuid).is_some())
+}
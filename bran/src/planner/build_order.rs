@@ -1,9 +1,7 @@
 use anyhow::{bail, Result};
 
-use starduck::AdditionOrder;
-use uuid::Uuid;
-
 use super::planner::ProblemInfo;
+use starduck::AdditionOrder;
 
 const DATAKEY: &str = "key:";
 
@@ -15,10 +13,15 @@ pub trait BuildOrder<T> {
 
 impl BuildOrder<ProblemInfo> for AdditionOrder {
     fn build_order(&mut self, t: &ProblemInfo) -> Result<()> {
-        // Add add device id from order
+        // Add the device id allocated for the order
+        let device_uuid = match t.device_uuid {
+            Some(uuid) => uuid,
+            None => bail!("No device uuid was allocated for {:?}", t),
+        };
+
         self.env_vars.insert(
             "device_uuid".to_owned(),
-            serde_json::Value::from(Uuid::new_v4().to_string()),
+            serde_json::Value::from(device_uuid.to_string()),
         );
 
         if let Some(k) = self.process_datakey(&t.data_requirement_key)? {
@@ -48,7 +51,7 @@ impl BuildOrder<ProblemInfo> for AdditionOrder {
         match datakeys.len() {
             1 => {
                 let (index, str) = &datakeys[0];
-                self.args.remove(index.clone());
+                self.args.remove(*index);
 
                 Ok(Some(str.replace(DATAKEY, format!("{}:", req_key).as_str())))
             }
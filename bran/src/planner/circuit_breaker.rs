@@ -0,0 +1,120 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::config::CircuitConfig;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+/// What the breaker lets the planner send on this cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permit {
+    All,
+    Probe,
+    None,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CircuitBreaker {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub failure_threshold: u32,
+    #[serde(with = "seconds")]
+    pub probe_interval: Duration,
+    pub opened_at: Option<DateTime<Utc>>,
+    pub last_probe: Option<DateTime<Utc>>,
+}
+
+impl CircuitBreaker {
+    pub fn new() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            failure_threshold: 5,
+            probe_interval: Duration::seconds(60),
+            opened_at: None,
+            last_probe: None,
+        }
+    }
+
+    /// Refreshes the thresholds from the config.
+    pub fn configure(&mut self, config: &CircuitConfig) {
+        self.failure_threshold = config.failure_threshold;
+        self.probe_interval = Duration::seconds(config.probe_interval_secs as i64);
+    }
+
+    pub fn permit(&mut self, now: DateTime<Utc>) -> Permit {
+        match self.state {
+            CircuitState::Closed => Permit::All,
+            CircuitState::HalfOpen => Permit::None,
+            CircuitState::Open => {
+                let since = self.last_probe.or(self.opened_at).unwrap_or(now);
+
+                if now - since < self.probe_interval {
+                    return Permit::None;
+                }
+
+                info!("Probing dothing after the circuit was opened");
+
+                self.state = CircuitState::HalfOpen;
+                self.last_probe = Some(now);
+                Permit::Probe
+            }
+        }
+    }
+
+    /// Goes back to open when the probe could not be sent.
+    pub fn cancel_probe(&mut self) {
+        if self.state == CircuitState::HalfOpen {
+            self.state = CircuitState::Open;
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.state == CircuitState::Open
+    }
+
+    pub fn on_success(&mut self) {
+        if self.state != CircuitState::Closed {
+            info!("dothing recovered. Closing circuit");
+        }
+
+        self.state = CircuitState::Closed;
+        self.consecutive_failures = 0;
+        self.opened_at = None;
+        self.last_probe = None;
+    }
+
+    pub fn on_failure(&mut self, now: DateTime<Utc>) {
+        self.consecutive_failures += 1;
+
+        match self.state {
+            CircuitState::HalfOpen => {
+                warn!("dothing probe failed. Keeping circuit open");
+                self.state = CircuitState::Open;
+            }
+            CircuitState::Closed if self.consecutive_failures >= self.failure_threshold => {
+                error!(
+                    "{} consecutive failures sending orders to dothing. Opening circuit",
+                    self.consecutive_failures
+                );
+                self.state = CircuitState::Open;
+                self.opened_at = Some(now);
+            }
+            _ => {}
+        }
+    }
+}
+
+mod seconds {
+    use chrono::Duration;
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(duration.num_seconds())
+    }
+}
@@ -0,0 +1,32 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+
+/// Where the planner reads the time from.
+#[derive(Debug, Clone, Default)]
+pub enum Clock {
+    #[default]
+    System,
+    /// Only moves when told to, so simulations don't wait on real time
+    Virtual(Arc<Mutex<DateTime<Utc>>>),
+}
+
+impl Clock {
+    pub fn starting_at(start: DateTime<Utc>) -> Self {
+        Self::Virtual(Arc::new(Mutex::new(start)))
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        match self {
+            Clock::System => Utc::now(),
+            Clock::Virtual(now) => *now.lock().unwrap(),
+        }
+    }
+
+    /// Moves a virtual clock to `at`. The system clock can't be moved.
+    pub fn set(&self, at: DateTime<Utc>) {
+        if let Clock::Virtual(now) = self {
+            *now.lock().unwrap() = at;
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use std::sync::{Arc, Mutex};
+
+use super::planned_order::PlannedOrder;
+
+/// Where the admitted orders go.
+#[derive(Debug, Clone, Default)]
+pub enum Dispatch {
+    /// Sent to the dothing target of their application
+    #[default]
+    Dothing,
+    /// Kept in the list instead, as if dothing took every one
+    Record(Arc<Mutex<Vec<PlannedOrder>>>),
+}
+
+impl Dispatch {
+    pub fn record() -> Self {
+        Self::Record(Arc::default())
+    }
+
+    /// The orders recorded since the last take.
+    pub fn take(&self) -> Vec<PlannedOrder> {
+        match self {
+            Dispatch::Dothing => Vec::new(),
+            Dispatch::Record(orders) => std::mem::take(&mut *orders.lock().unwrap()),
+        }
+    }
+}
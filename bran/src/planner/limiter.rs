@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::planned_order::PlannedOrder;
+use crate::config::LimitsConfig;
+
+/// Caps on how much remediation the planner may issue.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    pub per_cycle: Option<usize>,
+    pub in_flight: usize,
+    pub window: Duration,
+    pub per_app: Option<usize>,
+    pub per_location: Option<usize>,
+}
+
+impl From<&LimitsConfig> for Limits {
+    fn from(config: &LimitsConfig) -> Self {
+        Self {
+            per_cycle: config.max_actions_per_cycle,
+            in_flight: config.max_in_flight.max(1),
+            window: Duration::seconds(config.rate_window_secs as i64),
+            per_app: config.max_actions_per_app,
+            per_location: config.max_actions_per_location,
+        }
+    }
+}
+
+/// Sliding window of the orders the planner let through.
+pub struct RateLimiter {
+    history: VecDeque<(DateTime<Utc>, String, String)>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self {
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Whether `order` can go out without exceeding a limit, counting the
+    /// orders already admitted this cycle. Otherwise returns why it has to
+    /// wait. Nothing is spent until the order is [`recorded`](Self::record).
+    pub fn check(
+        &mut self,
+        limits: &Limits,
+        now: DateTime<Utc>,
+        order: &PlannedOrder,
+        admitted: &[PlannedOrder],
+    ) -> Result<(), String> {
+        while let Some((t, _, _)) = self.history.front() {
+            if now - *t < limits.window {
+                break;
+            }
+            self.history.pop_front();
+        }
+
+        if let Some(max) = limits.per_cycle {
+            if admitted.len() >= max {
+                return Err(format!("cycle limit of {} actions reached", max));
+            }
+        }
+
+        if let Some(max) = limits.per_app {
+            let count = self
+                .history
+                .iter()
+                .filter(|(_, app, _)| app == &order.app_name)
+                .count()
+                + admitted
+                    .iter()
+                    .filter(|o| o.app_name == order.app_name)
+                    .count();
+
+            if count >= max {
+                return Err(format!(
+                    "app {} used its budget of {} actions",
+                    order.app_name, max
+                ));
+            }
+        }
+
+        if let Some(max) = limits.per_location {
+            let count = self
+                .history
+                .iter()
+                .filter(|(_, app, loc)| {
+                    app == &order.app_name && loc == &order.problem.location_key
+                })
+                .count()
+                + admitted
+                    .iter()
+                    .filter(|o| {
+                        o.app_name == order.app_name
+                            && o.problem.location_key == order.problem.location_key
+                    })
+                    .count();
+
+            if count >= max {
+                return Err(format!(
+                    "location {} used its budget of {} actions",
+                    order.problem.location_key, max
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Spends the budgets on an order that was sent to dothing.
+    pub fn record(&mut self, now: DateTime<Utc>, order: &PlannedOrder) {
+        self.history.push_back((
+            now,
+            order.app_name.clone(),
+            order.problem.location_key.clone(),
+        ));
+    }
+}
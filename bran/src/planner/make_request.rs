@@ -1,26 +1,30 @@
-use anyhow::{bail, Result};
+use anyhow::{bail, Error, Result};
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::StatusCode;
 
 use starduck::{AdditionOrder, ReconfigureOrder, RestartOrder};
 
+use crate::aggregator::DothingTarget;
+
 #[async_trait]
 pub trait MakeRequest {
     const ENDPOINT: &'static str;
 
-    async fn make_request(&self, target: &str) -> Result<()>;
+    /// Sends the order, failing when dothing can't be reached or answers
+    /// with anything but a 2xx.
+    async fn make_request(&self, target: &DothingTarget) -> Result<()>;
 }
 
 #[async_trait]
 impl MakeRequest for AdditionOrder {
     const ENDPOINT: &'static str = "/addition";
 
-    async fn make_request(&self, target: &str) -> Result<()> {
-        let url = format!("{}{}", target, Self::ENDPOINT);
-
-        let client = Client::new();
-        match client.post(url).json(&self).send().await {
-            Ok(_) => return Ok(()),
+    async fn make_request(&self, target: &DothingTarget) -> Result<()> {
+        match target.post(Self::ENDPOINT)?.json(&self).send().await {
+            Ok(response) => {
+                response.error_for_status()?;
+                Ok(())
+            }
             Err(e) => bail!("{e}"),
         }
     }
@@ -30,12 +34,12 @@ impl MakeRequest for AdditionOrder {
 impl MakeRequest for RestartOrder {
     const ENDPOINT: &'static str = "/restart";
 
-    async fn make_request(&self, target: &str) -> Result<()> {
-        let url = format!("{}{}", target, Self::ENDPOINT);
-
-        let client = Client::new();
-        match client.post(url).json(&self).send().await {
-            Ok(_) => return Ok(()),
+    async fn make_request(&self, target: &DothingTarget) -> Result<()> {
+        match target.post(Self::ENDPOINT)?.json(&self).send().await {
+            Ok(response) => {
+                response.error_for_status()?;
+                Ok(())
+            }
             Err(e) => bail!("{e}"),
         }
     }
@@ -45,13 +49,25 @@ impl MakeRequest for RestartOrder {
 impl MakeRequest for ReconfigureOrder {
     const ENDPOINT: &'static str = "/reconfig/http";
 
-    async fn make_request(&self, target: &str) -> Result<()> {
-        let url = format!("{}{}", target, Self::ENDPOINT);
-
-        let client = Client::new();
-        match client.post(url).json(&self).send().await {
-            Ok(_) => return Ok(()),
+    async fn make_request(&self, target: &DothingTarget) -> Result<()> {
+        match target.post(Self::ENDPOINT)?.json(&self).send().await {
+            Ok(response) => {
+                response.error_for_status()?;
+                Ok(())
+            }
             Err(e) => bail!("{e}"),
         }
     }
 }
+
+/// Whether a failed send says something about the health of dothing.
+/// Orders it refuses as invalid don't, unless it is asking to slow down.
+pub fn counts_against_circuit(error: &Error) -> bool {
+    match error
+        .downcast_ref::<reqwest::Error>()
+        .and_then(|e| e.status())
+    {
+        Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+        None => true,
+    }
+}
@@ -1,5 +1,18 @@
+mod allocations;
 mod build_order;
+mod circuit_breaker;
+mod clock;
+mod dispatch;
+mod limiter;
 mod make_request;
+mod planned_order;
+#[allow(clippy::module_inception)]
 mod planner;
+mod planner_state;
 
-pub(crate) use planner::Planner;
+pub use allocations::AllocationStatus;
+pub use clock::Clock;
+pub use dispatch::Dispatch;
+pub use planned_order::{Order, OrderKind, PlannedOrder, PlannedOrderView};
+pub use planner::{Planner, ProblemInfo};
+pub use planner_state::{CycleSummary, PlannerState};
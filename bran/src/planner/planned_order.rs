@@ -0,0 +1,180 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use uuid::Uuid;
+
+use starduck::{AdditionOrder, ReconfigureOrder, RestartOrder};
+
+use super::allocations::Allocations;
+use super::build_order::BuildOrder;
+use super::make_request::MakeRequest;
+use super::planner::ProblemInfo;
+use crate::aggregator::DothingTarget;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OrderKind {
+    Addition,
+    Restart,
+    Reconfigure,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Order {
+    Addition(AdditionOrder),
+    Restart(RestartOrder),
+    Reconfigure(ReconfigureOrder),
+}
+
+impl Order {
+    pub fn kind(&self) -> OrderKind {
+        match self {
+            Order::Addition(_) => OrderKind::Addition,
+            Order::Restart(_) => OrderKind::Restart,
+            Order::Reconfigure(_) => OrderKind::Reconfigure,
+        }
+    }
+
+    pub async fn send(&self, target: &DothingTarget) -> Result<()> {
+        match self {
+            Order::Addition(order) => order.make_request(target).await,
+            Order::Restart(order) => order.make_request(target).await,
+            Order::Reconfigure(order) => order.make_request(target).await,
+        }
+    }
+}
+
+/// An order the planner wants to send for a problem in an application.
+#[derive(Debug, Clone)]
+pub struct PlannedOrder {
+    pub app_name: String,
+    pub problem: ProblemInfo,
+    pub order: Order,
+    pub target: DothingTarget,
+    /// Set when the order was deferred on an earlier cycle
+    pub queued_since: Option<DateTime<Utc>>,
+}
+
+impl PlannedOrder {
+    pub fn new(
+        app_name: &str,
+        problem: &ProblemInfo,
+        order: Order,
+        target: &DothingTarget,
+    ) -> Self {
+        Self {
+            app_name: app_name.to_string(),
+            problem: problem.clone(),
+            order,
+            target: target.clone(),
+            queued_since: None,
+        }
+    }
+
+    pub fn key(&self) -> (String, ProblemInfo, OrderKind) {
+        (
+            self.app_name.clone(),
+            self.problem.clone(),
+            self.order.kind(),
+        )
+    }
+
+    /// The order as it would go out. Additions are built with the device
+    /// uuid `allocations` would hand out next, which fails when their
+    /// directive can't be filled in.
+    pub fn build(&self, allocations: &Allocations) -> Result<Self> {
+        let mut built = self.clone();
+
+        if let Order::Addition(order) = &mut built.order {
+            built.problem.device_uuid = Some(allocations.next_uuid(&self.app_name, &self.problem));
+
+            info!(
+                app = %built.app_name,
+                location = %built.problem.location_key,
+                order_kind = "Addition",
+                "Building addition order from {:?}",
+                &built.problem
+            );
+            order.build_order(&built.problem)?;
+        }
+
+        Ok(built)
+    }
+
+    /// [`Self::key`] as planned. Additions are planned without a device
+    /// uuid, they only get one once admitted.
+    pub fn planned_key(&self) -> (String, ProblemInfo, OrderKind) {
+        let mut problem = self.problem.clone();
+
+        if self.order.kind() == OrderKind::Addition {
+            problem.device_uuid = None;
+        }
+
+        (self.app_name.clone(), problem, self.order.kind())
+    }
+
+    /// The order as shown to operators, without the dothing credentials.
+    pub fn view(&self) -> PlannedOrderView {
+        PlannedOrderView {
+            app_name: self.app_name.clone(),
+            problem: self.problem.clone(),
+            kind: self.order.kind(),
+            target: self.target.url.clone(),
+            order: self.order.clone(),
+        }
+    }
+
+    /// The order as it waits in the deferred queue. It keeps its place if
+    /// it was already queued, otherwise it joins at `now`.
+    pub fn defer(&self, now: DateTime<Utc>, reason: String) -> DeferredOrder {
+        let (app_name, problem, kind) = self.planned_key();
+
+        DeferredOrder {
+            app_name,
+            problem,
+            kind,
+            deferred_since: self.queued_since.unwrap_or(now),
+            reason,
+        }
+    }
+
+    /// Orders by application, location, data requirement and device.
+    pub fn sort_key(&self) -> (&str, &str, &str, Option<Uuid>) {
+        (
+            &self.app_name,
+            &self.problem.location_key,
+            &self.problem.data_requirement_key,
+            self.problem.device_uuid,
+        )
+    }
+}
+
+/// An order planned on the last cycle. Additions show their directive as
+/// set, they only get a device uuid once admitted.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedOrderView {
+    pub app_name: String,
+    pub problem: ProblemInfo,
+    pub kind: OrderKind,
+    pub target: Url,
+    pub order: Order,
+}
+
+/// An order held back by the rate limits or an open circuit. It keeps its
+/// place in the queue for as long as the planner keeps finding the same
+/// problem.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeferredOrder {
+    pub app_name: String,
+    pub problem: ProblemInfo,
+    pub kind: OrderKind,
+    pub deferred_since: DateTime<Utc>,
+    pub reason: String,
+}
+
+impl DeferredOrder {
+    pub fn key(&self) -> (String, ProblemInfo, OrderKind) {
+        (self.app_name.clone(), self.problem.clone(), self.kind)
+    }
+}
@@ -1,16 +1,32 @@
-use std::collections::HashMap;
-use std::env;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
+use anyhow::{bail, Context, Result};
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{watch, Semaphore};
+use tokio::task::JoinSet;
+use tracing::Instrument;
+use url::Url;
 use uuid::Uuid;
 
-use crate::aggregator::ApplicationRegister;
-use crate::planner::build_order::BuildOrder;
-use crate::planner::make_request::MakeRequest;
+use crate::aggregator::{desired_view, has_shortfall, ApplicationRegister, DothingTarget};
+use crate::config::PlannerConfig;
+use crate::events::{EventBus, EventKind};
+use crate::metrics::Metrics;
+use crate::planner::circuit_breaker::Permit;
+use crate::planner::limiter::{Limits, RateLimiter};
+use crate::planner::make_request::counts_against_circuit;
+use crate::planner::planned_order::{Order, PlannedOrder};
+use crate::planner::planner_state::AWAITING_APPROVAL;
+use crate::planner::{Clock, CycleSummary, Dispatch, PlannerState};
+use crate::shutdown::Shutdown;
 
-use starduck::{Location, Status};
+use starduck::{Directives, Location, Status};
 
-#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+const CIRCUIT_OPEN: &str = "dothing circuit is open";
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
 pub struct ProblemInfo {
     pub location_key: String,
     pub data_requirement_key: String,
@@ -22,11 +38,12 @@ impl ProblemInfo {
         Self {
             location_key: location_key.to_string(),
             data_requirement_key: data_key.to_string(),
-            device_uuid: device_uuid.clone(),
+            device_uuid: *device_uuid,
         }
     }
 }
 
+#[derive(Debug)]
 enum Action {
     Addition(usize),
     Restart,
@@ -35,62 +52,188 @@ enum Action {
 
 pub struct Planner {
     register: Arc<Mutex<ApplicationRegister>>,
+    state: Arc<Mutex<PlannerState>>,
+    events: Arc<EventBus>,
+    metrics: Arc<Metrics>,
+    config: PlannerConfig,
+    updates: watch::Receiver<PlannerConfig>,
     problem_action: HashMap<ProblemInfo, Action>,
+    limiter: RateLimiter,
+    shutdown: Shutdown,
+    /// Cycles run so far
+    cycles: u64,
+    clock: Clock,
+    dispatch: Dispatch,
 }
 
 impl Planner {
-    const DOTHING: &str = "dothing";
-    const WATCHER_DELAY: &str = "watcher_delay";
-    const INTERVAL: &str = "watcher_interval";
+    pub fn new(
+        register: Arc<Mutex<ApplicationRegister>>,
+        state: Arc<Mutex<PlannerState>>,
+        events: Arc<EventBus>,
+        metrics: Arc<Metrics>,
+        mut updates: watch::Receiver<PlannerConfig>,
+        shutdown: Shutdown,
+    ) -> Self {
+        let config = updates.borrow_and_update().clone();
 
-    pub fn new(register: Arc<Mutex<ApplicationRegister>>) -> Self {
         Self {
             register,
+            state,
+            events,
+            metrics,
+            config,
+            updates,
             problem_action: HashMap::new(),
+            limiter: RateLimiter::new(),
+            shutdown,
+            cycles: 0,
+            clock: Clock::System,
+            dispatch: Dispatch::Dothing,
         }
     }
 
-    pub async fn watch_over(&mut self) {
-        let wait = env::var(Self::WATCHER_DELAY)
-            .map(|k| std::time::Duration::from_secs(k.parse().unwrap()))
-            .unwrap_or(std::time::Duration::from_secs(0));
+    /// Reads the time from `clock` instead of the system.
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
 
-        let interval = env::var(Self::INTERVAL)
-            .map(|k| std::time::Duration::from_secs(k.parse().unwrap()))
-            .unwrap_or(std::time::Duration::from_secs(120));
+    /// Hands the admitted orders to `dispatch` instead of dothing.
+    pub fn with_dispatch(mut self, dispatch: Dispatch) -> Self {
+        self.dispatch = dispatch;
+        self
+    }
+
+    /// Runs planning cycles until shutdown. A cycle already running is let
+    /// finish, so its orders are not cut off halfway.
+    pub async fn watch_over(&mut self) {
+        let wait = std::time::Duration::from_secs(self.config.delay_secs);
 
-        std::thread::sleep(wait);
+        if !self.pause(wait).await {
+            return;
+        }
 
         loop {
-            info!("Starting Planner Execution");
+            self.step().await;
+
+            let interval = std::time::Duration::from_secs(self.config.interval_secs);
+            if !self.pause(interval).await {
+                break;
+            }
+        }
 
-            self.execute_actions().await;
+        info!("Planner stopped");
+    }
 
-            std::thread::sleep(interval);
+    /// Runs a single planning cycle and returns how many orders it
+    /// dispatched. Reloaded settings apply from here.
+    pub async fn step(&mut self) -> usize {
+        if self.updates.has_changed().unwrap_or(false) {
+            self.config = self.updates.borrow_and_update().clone();
+            info!("Planner settings reloaded");
         }
+
+        self.cycles += 1;
+        let cycle = self.cycles;
+
+        let interval = std::time::Duration::from_secs(self.config.interval_secs);
+        self.state.lock().unwrap().interval = Duration::from_std(interval).unwrap();
+
+        let span = info_span!("planner_cycle", cycle);
+
+        span.in_scope(|| info!("Starting Planner Execution"));
+        self.events.publish(EventKind::PlannerCycleStarted);
+
+        let started_at = self.clock.now();
+        let started = std::time::Instant::now();
+        let orders = self.execute_actions().instrument(span).await;
+        let duration = started.elapsed();
+
+        self.metrics.cycle_duration.observe(duration.as_secs_f64());
+        self.metrics
+            .last_cycle
+            .set(self.clock.now().timestamp() as f64);
+
+        self.state.lock().unwrap().last_cycle = Some(CycleSummary {
+            number: cycle,
+            started_at,
+            finished_at: self.clock.now(),
+            duration_ms: duration.as_millis() as i64,
+            orders,
+        });
+
+        self.events.publish(EventKind::PlannerCycleFinished {
+            duration_ms: duration.as_millis() as i64,
+            orders,
+        });
+
+        orders
     }
 
-    async fn execute_actions(&mut self) {
-        let applications = self
-            .register
-            .lock()
-            .unwrap()
-            .apps
-            .iter()
-            .filter_map(|(_, app)| {
-                if app.status != Status::Coherent && app.status != Status::Uninitialized {
-                    return Some(app);
-                } else {
-                    None
-                }
-            })
-            .cloned()
-            .collect::<Vec<_>>();
+    /// Sleeps for `duration`, returning false if shutdown started meanwhile.
+    async fn pause(&self, duration: std::time::Duration) -> bool {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => true,
+            _ = self.shutdown.wait() => false,
+        }
+    }
+
+    /// Runs one planning cycle and returns how many orders were dispatched.
+    async fn execute_actions(&mut self) -> usize {
+        self.reconcile_allocations();
 
-        let target = env::var(Self::DOTHING).unwrap_or("http://dothing:8050".to_owned());
+        let planned = self.plan();
+        self.state.lock().unwrap().planned = planned.iter().map(PlannedOrder::view).collect();
+
+        let admitted = self.admit_orders(planned);
+        let orders = admitted.len();
+
+        self.dispatch_orders(admitted).await;
+
+        orders
+    }
+
+    /// The orders that would fix the problems currently in the register,
+    /// before any rate limit.
+    pub fn plan(&mut self) -> Vec<PlannedOrder> {
+        let applications = {
+            let guard = self.register.lock().unwrap();
+
+            guard
+                .specs
+                .iter()
+                .filter_map(|(name, spec)| {
+                    let observed = guard.statuses.get(name)?;
+                    Some(desired_view(spec, observed))
+                })
+                .filter(|app| app.status != Status::Uninitialized)
+                .filter(|app| app.status != Status::Coherent || has_shortfall(&app.locations))
+                .collect::<Vec<_>>()
+        };
+
+        let mut planned = Vec::new();
 
         for app in applications {
-            info!("Checking Application {}", &app.name);
+            // Ties the orders to the state report that triggered them
+            let report_id = self
+                .register
+                .lock()
+                .unwrap()
+                .report_ids
+                .get(&app.name)
+                .cloned();
+            let _span = info_span!("app", app = %app.name, report_id).entered();
+
+            info!(app = %app.name, "Checking Application {}", &app.name);
+
+            let target = match self.resolve_target(&app.name) {
+                Ok(target) => target,
+                Err(e) => {
+                    error!(app = %app.name, "{e}");
+                    continue;
+                }
+            };
 
             let hash_directives = {
                 let guard = &self.register.lock().unwrap();
@@ -98,105 +241,593 @@ impl Planner {
             };
 
             if let Some(directives) = hash_directives.get(&app.name) {
-                for problem in self.find_problems("root", &app.locations) {
-                    match problem {
-                        (Action::Addition(count), p) => {
-                            if let Some(Some(order)) =
-                                directives.get(&p.location_key).map(|d| d.addition.clone())
-                            {
-                                info!("Executing Addition order");
-
-                                for i in 1..=count {
-                                    let mut mod_order = order.clone();
-
-                                    info!(
-                                        "Building addition order {} out of {} from {:?}",
-                                        i, count, &p
-                                    );
-                                    if let Err(e) = mod_order.build_order(&p) {
-                                        error!("{e}");
-                                        continue;
-                                    }
-
-                                    info!("Executing  order {} out of {}", i, count);
-
-                                    if let Err(e) = mod_order.make_request(&target).await {
-                                        error!("{e}");
-                                        continue;
-                                    };
-                                }
-                                continue;
-                            }
+                for problem in self.find_problems(&app.name, "root", &app.locations) {
+                    planned.extend(self.plan_orders(&app.name, directives, &target, problem));
+                }
+            } else {
+                warn!(app = %app.name, "No directives for {}!", app.name);
+            }
+        }
 
-                            warn!(
-                                "No Addition directive for {} in app {}!",
-                                &p.location_key, &app.name
-                            );
-                        }
-                        (Action::Reconfigure, p) => {
-                            info!("Executing Reconfigure order");
+        // Same register, same orders in the same order. Limits and device
+        // uuids then go the same way on every run
+        planned.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
 
-                            if let Some(Some(order)) =
-                                directives.get(&p.location_key).map(|d| d.reconfig.clone())
-                            {
-                                self.problem_action.insert(p.clone(), Action::Reconfigure);
+        planned
+    }
 
-                                let mut mod_order = order.clone();
-                                mod_order.uuid = Some(p.device_uuid.unwrap());
+    /// Finds where the orders of `app_name` go. Applications without a target
+    /// of their own fall back to the configured default.
+    fn resolve_target(&self, app_name: &str) -> Result<DothingTarget> {
+        let target = match self.register.lock().unwrap().targets.get(app_name) {
+            Some(target) => target.clone(),
+            None => match self.config.default_target() {
+                Some(target) => target,
+                None => bail!("No dothing target configured for app {}", app_name),
+            },
+        };
+
+        target
+            .validate()
+            .with_context(|| format!("Invalid dothing target for app {}", app_name))?;
+
+        Ok(target)
+    }
 
-                                info!("Executing order: {:?}", &mod_order);
+    fn plan_orders(
+        &self,
+        app_name: &str,
+        directives: &HashMap<String, Directives>,
+        target: &DothingTarget,
+        problem: (Action, ProblemInfo),
+    ) -> Vec<PlannedOrder> {
+        let _span = info_span!(
+            "action",
+            action = ?problem.0,
+            location = %problem.1.location_key,
+            data_key = %problem.1.data_requirement_key,
+            device_uuid = ?problem.1.device_uuid,
+        )
+        .entered();
+
+        match problem {
+            (Action::Addition(count), p) => {
+                if let Some(Some(order)) =
+                    directives.get(&p.location_key).map(|d| d.addition.clone())
+                {
+                    if self
+                        .state
+                        .lock()
+                        .unwrap()
+                        .allocations
+                        .has_failed(app_name, &p)
+                    {
+                        warn!(
+                            app = %app_name,
+                            location = %p.location_key,
+                            order_kind = "Addition",
+                            "Previous addition for {} in {} failed to deploy. Skipping until it is cleared",
+                            &p.data_requirement_key, &p.location_key
+                        );
+                        return Vec::new();
+                    }
 
-                                if let Err(e) = mod_order.make_request(&target).await {
-                                    error!("{e}");
-                                };
-                                continue;
-                            }
+                    info!(
+                        app = %app_name,
+                        location = %p.location_key,
+                        order_kind = "Addition",
+                        "Planning {} Addition orders",
+                        count
+                    );
 
-                            warn!(
-                                "No Reconfigure directive for {} in app {}!",
-                                &p.location_key, &app.name
-                            );
-                        }
-                        (Action::Restart, p) => {
-                            info!("Executing Restart order");
+                    return (1..=count)
+                        .map(|_| {
+                            PlannedOrder::new(app_name, &p, Order::Addition(order.clone()), target)
+                        })
+                        .collect();
+                }
 
-                            if let Some(Some(order)) =
-                                directives.get(&p.location_key).map(|d| d.restart.clone())
-                            {
-                                self.problem_action.insert(p.clone(), Action::Restart);
+                warn!(
+                    app = %app_name,
+                    location = %p.location_key,
+                    order_kind = "Addition",
+                    "No Addition directive for {} in app {}!",
+                    &p.location_key, app_name
+                );
+            }
+            (Action::Reconfigure, p) => {
+                info!(
+                    app = %app_name,
+                    location = %p.location_key,
+                    order_kind = "Reconfigure",
+                    "Planning Reconfigure order"
+                );
+
+                if let Some(Some(order)) =
+                    directives.get(&p.location_key).map(|d| d.reconfig.clone())
+                {
+                    let mut mod_order = order.clone();
+                    mod_order.uuid = Some(p.device_uuid.unwrap());
+
+                    return vec![PlannedOrder::new(
+                        app_name,
+                        &p,
+                        Order::Reconfigure(mod_order),
+                        target,
+                    )];
+                }
 
-                                let mut mod_order = order.clone();
-                                mod_order.uuid = Some(p.device_uuid.unwrap());
+                warn!(
+                    app = %app_name,
+                    location = %p.location_key,
+                    order_kind = "Reconfigure",
+                    "No Reconfigure directive for {} in app {}!",
+                    &p.location_key, app_name
+                );
+            }
+            (Action::Restart, p) => {
+                info!(
+                    app = %app_name,
+                    location = %p.location_key,
+                    order_kind = "Restart",
+                    "Planning Restart order"
+                );
+
+                if let Some(Some(order)) =
+                    directives.get(&p.location_key).map(|d| d.restart.clone())
+                {
+                    let mut mod_order = order.clone();
+                    mod_order.uuid = Some(p.device_uuid.unwrap());
+
+                    return vec![PlannedOrder::new(
+                        app_name,
+                        &p,
+                        Order::Restart(mod_order),
+                        target,
+                    )];
+                }
 
-                                info!("Executing order: {:?}", &mod_order);
+                warn!(
+                    app = %app_name,
+                    location = %p.location_key,
+                    order_kind = "Restart",
+                    "No Restart directive for {} in app {}!",
+                    &p.location_key, app_name
+                );
+            }
+        }
 
-                                if let Err(e) = mod_order.make_request(&target).await {
-                                    error!("{e}");
-                                };
-                                continue;
-                            }
+        Vec::new()
+    }
+
+    /// Applies the rate limits to this cycle's orders. The queue of orders
+    /// deferred on previous cycles goes first, in the order they were
+    /// deferred, and the orders held back join the back of it.
+    fn admit_orders(&mut self, planned: Vec<PlannedOrder>) -> Vec<PlannedOrder> {
+        let now = self.clock.now();
+        let limits = Limits::from(&self.config.limits);
+
+        let mut state = self.state.lock().unwrap();
+
+        // Each queued order takes the one planned this cycle for the same
+        // problem, so it goes out with the directives as they are now.
+        // Orders whose problem went away leave the queue
+        let mut queue = std::mem::take(&mut state.deferred);
+        queue.sort_by_key(|d| d.deferred_since);
+
+        let mut fresh = planned;
+        let mut ordered = Vec::new();
+
+        for queued in queue {
+            match fresh.iter().position(|o| o.key() == queued.key()) {
+                Some(i) => {
+                    let mut planned_order = fresh.remove(i);
+                    planned_order.queued_since = Some(queued.deferred_since);
+                    ordered.push(planned_order);
+                }
+                None => info!(
+                    app = %queued.app_name,
+                    location = %queued.problem.location_key,
+                    order_kind = ?queued.kind,
+                    "Dropping queued {:?} order for {:?} in app {}, the problem is gone",
+                    queued.kind, queued.problem, queued.app_name
+                ),
+            }
+        }
+
+        ordered.extend(fresh);
+
+        let mut deferred = Vec::new();
+
+        // Approvals of orders that were not planned again are dropped with
+        // them
+        let planned_keys = ordered
+            .iter()
+            .map(PlannedOrder::planned_key)
+            .collect::<HashSet<_>>();
+        state.approved.retain(|key| planned_keys.contains(key));
+
+        if self.config.require_approval {
+            let (approved, awaiting): (Vec<_>, Vec<_>) = ordered
+                .into_iter()
+                .partition(|o| state.approved.contains(&o.planned_key()));
+
+            if !awaiting.is_empty() {
+                info!("{} orders are awaiting approval", awaiting.len());
+            }
+
+            deferred.extend(
+                awaiting
+                    .iter()
+                    .map(|o| o.defer(now, AWAITING_APPROVAL.to_owned())),
+            );
+            ordered = approved;
+        }
+
+        // Each dothing target has its own circuit
+        let mut permits = HashMap::new();
+        let mut probes = HashSet::new();
+
+        for planned_order in &ordered {
+            let url = planned_order.target.url.to_string();
+
+            permits.entry(url).or_insert_with_key(|url| {
+                let circuit = state.circuit(url);
+                circuit.configure(&self.config.circuit);
+                circuit.permit(now)
+            });
+        }
+
+        for (url, permit) in &permits {
+            if *permit == Permit::None {
+                let count = ordered
+                    .iter()
+                    .filter(|o| &o.target.url.to_string() == url)
+                    .count();
+
+                warn!(
+                    "Circuit for dothing at {} is open. Holding back {} orders",
+                    url, count
+                );
+            }
+        }
+
+        let mut admitted = Vec::new();
+
+        for planned_order in ordered {
+            let url = planned_order.target.url.to_string();
+
+            let permit = match permits[&url] {
+                Permit::None => Err(CIRCUIT_OPEN.to_owned()),
+                Permit::Probe if probes.contains(&url) => {
+                    Err("waiting on dothing probe".to_owned())
+                }
+                _ => Ok(()),
+            };
+
+            if let Err(reason) = permit {
+                deferred.push(planned_order.defer(now, reason));
+                continue;
+            }
 
-                            warn!(
-                                "No Restart directive for {} in app {}!",
-                                &p.location_key, &app.name
-                            );
+            // Additions are built with the device uuid they would get, so one
+            // that can't be built spends neither a uuid nor a budget, and
+            // waits for its directives to be fixed
+            let built = match planned_order.build(&state.allocations) {
+                Ok(built) => built,
+                Err(e) => {
+                    deferred.push(planned_order.defer(now, format!("could not be built: {e}")));
+                    continue;
+                }
+            };
+
+            if let Err(reason) = self.limiter.check(&limits, now, &built, &admitted) {
+                deferred.push(planned_order.defer(now, reason));
+                continue;
+            }
+
+            if let Order::Addition(_) = built.order {
+                state
+                    .allocations
+                    .allocate(&built.app_name, &planned_order.problem);
+            }
+
+            if permits[&url] == Permit::Probe {
+                probes.insert(url);
+            }
+
+            admitted.push(built);
+        }
+
+        // Nothing went out as a probe, try again on the next probe interval
+        for (url, permit) in &permits {
+            if *permit == Permit::Probe && !probes.contains(url) {
+                state.circuit(url).cancel_probe();
+            }
+        }
+
+        // Circuits and approvals are reported on their own
+        let held_back = deferred
+            .iter()
+            .filter(|d| d.reason != CIRCUIT_OPEN && d.reason != AWAITING_APPROVAL)
+            .collect::<Vec<_>>();
+
+        if !held_back.is_empty() {
+            warn!(
+                "Deferred {} orders, {} admitted this cycle",
+                held_back.len(),
+                admitted.len()
+            );
+
+            for d in held_back {
+                warn!(
+                    app = %d.app_name,
+                    location = %d.problem.location_key,
+                    order_kind = ?d.kind,
+                    "Deferred {:?} order for {:?} in app {}: {}",
+                    d.kind, d.problem, d.app_name, d.reason
+                );
+            }
+        }
+
+        state.deferred = deferred;
+
+        admitted
+    }
+
+    async fn dispatch_orders(&mut self, admitted: Vec<PlannedOrder>) {
+        let now = self.clock.now();
+        let limits = Limits::from(&self.config.limits);
+        let semaphore = Arc::new(Semaphore::new(limits.in_flight));
+
+        let mut tasks = JoinSet::new();
+        let total = admitted.len();
+
+        for (i, planned_order) in admitted.into_iter().enumerate() {
+            let semaphore = Arc::clone(&semaphore);
+            let state = Arc::clone(&self.state);
+            let metrics = Arc::clone(&self.metrics);
+            let shutdown = self.shutdown.clone();
+            let dispatch = self.dispatch.clone();
+            let clock = self.clock.clone();
+
+            let span = info_span!(
+                "order",
+                app = %planned_order.app_name,
+                location = %planned_order.problem.location_key,
+                data_key = %planned_order.problem.data_requirement_key,
+                device_uuid = ?planned_order.problem.device_uuid,
+                order_kind = ?planned_order.order.kind(),
+            );
+
+            let task = async move {
+                let _permit = semaphore.acquire_owned().await;
+
+                // Orders already sent get to finish, queued ones wait for
+                // the next run
+                if shutdown.is_triggered() {
+                    return (planned_order, None);
+                }
+
+                // The circuit may have opened while this order was waiting
+                let url = planned_order.target.url.to_string();
+                if state.lock().unwrap().circuit(&url).is_open() {
+                    return (planned_order, None);
+                }
+
+                info!("Executing order {} out of {}", i + 1, total);
+                info!("Executing order: {:?}", &planned_order.order);
+
+                let started = std::time::Instant::now();
+                let result = match &dispatch {
+                    Dispatch::Dothing => planned_order.order.send(&planned_order.target).await,
+                    Dispatch::Record(orders) => {
+                        orders.lock().unwrap().push(planned_order.clone());
+                        Ok(())
+                    }
+                };
+
+                metrics
+                    .dothing_latency
+                    .with_label_values(&[&format!("{:?}", planned_order.order.kind())])
+                    .observe(started.elapsed().as_secs_f64());
+
+                // Before the permit is released, so the orders still waiting
+                // on it see the circuit open
+                let opened = {
+                    let mut state = state.lock().unwrap();
+                    let circuit = state.circuit(&url);
+
+                    match &result {
+                        Ok(_) => {
+                            circuit.on_success();
+                            false
                         }
+                        // Refused, but dothing is up to answer
+                        Err(e) if !counts_against_circuit(e) => {
+                            circuit.on_success();
+                            false
+                        }
+                        Err(_) => {
+                            let was_open = circuit.is_open();
+                            circuit.on_failure(clock.now());
+                            !was_open && circuit.is_open()
+                        }
+                    }
+                };
+
+                (planned_order, Some((result, opened)))
+            };
+
+            tasks.spawn(task.instrument(span));
+        }
+
+        let mut opened = Vec::new();
+
+        while let Some(joined) = tasks.join_next().await {
+            let (planned_order, result) = match joined {
+                Ok((planned_order, Some((result, circuit_opened)))) => {
+                    self.limiter.record(self.clock.now(), &planned_order);
+                    self.state
+                        .lock()
+                        .unwrap()
+                        .approved
+                        .remove(&planned_order.planned_key());
+
+                    if circuit_opened {
+                        opened.push(planned_order.target.url.clone());
+                    }
+
+                    (planned_order, result)
+                }
+                Ok((planned_order, None)) => {
+                    let reason = if self.shutdown.is_triggered() {
+                        "bran shutting down"
+                    } else {
+                        "dothing circuit opened"
+                    };
+
+                    self.state
+                        .lock()
+                        .unwrap()
+                        .deferred
+                        .push(planned_order.defer(now, reason.to_owned()));
+                    continue;
+                }
+                Err(e) => {
+                    error!("{e}");
+                    continue;
+                }
+            };
+
+            if let Err(e) = &result {
+                error!(
+                    app = %planned_order.app_name,
+                    location = %planned_order.problem.location_key,
+                    order_kind = ?planned_order.order.kind(),
+                    "{e}"
+                );
+            }
+
+            let outcome = if result.is_ok() { "success" } else { "failure" };
+            self.metrics
+                .orders
+                .with_label_values(&[&format!("{:?}", planned_order.order.kind()), outcome])
+                .inc();
+
+            self.events.publish(match &result {
+                Ok(_) => EventKind::OrderSent {
+                    app: planned_order.app_name.clone(),
+                    problem: planned_order.problem.clone(),
+                    kind: planned_order.order.kind(),
+                },
+                Err(e) => EventKind::OrderFailed {
+                    app: planned_order.app_name.clone(),
+                    problem: planned_order.problem.clone(),
+                    kind: planned_order.order.kind(),
+                    error: e.to_string(),
+                },
+            });
+
+            match &planned_order.order {
+                Order::Addition(order) => {
+                    // Only additions dothing took are waited on, refused
+                    // ones are planned again on the next cycle
+                    if result.is_ok() {
+                        self.state.lock().unwrap().allocations.record(
+                            &planned_order.app_name,
+                            &planned_order.problem,
+                            order,
+                            self.clock.now(),
+                        );
                     }
                 }
-            } else {
-                warn!("No directives for {}!", app.name);
+                Order::Reconfigure(_) => {
+                    self.problem_action
+                        .insert(planned_order.problem, Action::Reconfigure);
+                }
+                Order::Restart(_) => {
+                    self.problem_action
+                        .insert(planned_order.problem, Action::Restart);
+                }
+            }
+        }
+
+        for url in opened {
+            self.events.publish(EventKind::CircuitOpened {
+                target: url.to_string(),
+                apps: self.apps_targeting(&url),
+            });
+        }
+    }
+
+    fn apps_targeting(&self, url: &Url) -> Vec<String> {
+        let apps = self
+            .register
+            .lock()
+            .unwrap()
+            .specs
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        apps.into_iter()
+            .filter(|app| {
+                self.resolve_target(app)
+                    .is_ok_and(|target| &target.url == url)
+            })
+            .collect()
+    }
+
+    fn reconcile_allocations(&self) {
+        let timeout = Duration::seconds(self.config.addition_timeout_secs as i64);
+
+        let applications = self
+            .register
+            .lock()
+            .unwrap()
+            .statuses
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut state = self.state.lock().unwrap();
+
+        for app in applications {
+            let failed = state.allocations.reconcile(&app, timeout, self.clock.now());
+
+            if !failed.is_empty() {
+                warn!(
+                    "{} additions in app {} were flagged as failed deployments",
+                    failed.len(),
+                    &app.name
+                );
+            }
+
+            for allocation in failed {
+                self.events.publish(EventKind::EscalationExhausted {
+                    app: app.name.clone(),
+                    problem: allocation.problem,
+                });
             }
         }
     }
 
-    fn find_problems(&self, location_key: &str, location: &Location) -> Vec<(Action, ProblemInfo)> {
+    fn find_problems(
+        &self,
+        app_name: &str,
+        location_key: &str,
+        location: &Location,
+    ) -> Vec<(Action, ProblemInfo)> {
         let mut report = Vec::new();
 
         if location.locations.is_empty() {
             let nc_data_req = location
                 .data_requirements
                 .iter()
-                .filter(|(_, data)| data.status != Status::Coherent)
+                .filter(|(_, data)| {
+                    data.status != Status::Coherent || data.count > data.components.len()
+                })
                 .collect::<Vec<_>>();
 
             warn!(
@@ -208,14 +839,31 @@ impl Planner {
             for (data_key, data_req) in nc_data_req {
                 let comp_count = data_req.components.len();
 
+                // Additions already sent that haven't shown up yet
+                let mut pending = self
+                    .state
+                    .lock()
+                    .unwrap()
+                    .allocations
+                    .pending_count(app_name, &ProblemInfo::new(location_key, data_key, &None));
+
                 // Missing services, has to add more
                 if data_req.count > comp_count {
+                    let missing_count = (data_req.count - comp_count).saturating_sub(pending);
+
+                    if missing_count == 0 {
+                        info!(
+                            "Waiting on {} pending additions for data requirement {} in {}",
+                            pending, data_key, location_key
+                        );
+                        continue;
+                    }
+
                     info!(
                         "Creating Addition Order for data requirement {} in {}",
                         data_key, location_key
                     );
 
-                    let missing_count = data_req.count - comp_count;
                     let problem_info = ProblemInfo::new(location_key, data_key, &None);
                     report.push((Action::Addition(missing_count), problem_info));
 
@@ -233,6 +881,14 @@ impl Planner {
 
                                 report.push((Action::Reconfigure, problem_info))
                             }
+                            Some(_) if pending > 0 => {
+                                info!(
+                                    "Waiting on a pending replacement for component {} in data requirement {} in {}",
+                                    comp.uuid.unwrap(), data_key, location_key
+                                );
+
+                                pending -= 1;
+                            }
                             Some(_) => {
                                 info!(
                                     "Creating Addition Order for data requirement {} in {}",
@@ -256,7 +912,7 @@ impl Planner {
             }
         } else if location.data_requirements.is_empty() {
             for (key, i_loc) in &location.locations {
-                report.extend(self.find_problems(key, i_loc));
+                report.extend(self.find_problems(app_name, key, i_loc));
             }
         }
         report
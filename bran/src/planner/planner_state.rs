@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::allocations::{AllocationStatus, Allocations};
+use super::circuit_breaker::CircuitBreaker;
+use super::planned_order::{DeferredOrder, OrderKind, PlannedOrderView};
+use super::planner::ProblemInfo;
+
+/// Why orders wait when approval is required.
+pub const AWAITING_APPROVAL: &str = "awaiting approval";
+
+/// Extra time a cycle may run late before the planner is considered stalled.
+const STALL_GRACE_SECS: i64 = 60;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CycleSummary {
+    pub number: u64,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: i64,
+    pub orders: usize,
+}
+
+/// What the planner keeps across restarts. Without the allocations every
+/// addition still deploying would be ordered again. Circuits start closed,
+/// so a restart tries dothing right away.
+#[derive(Serialize, Deserialize)]
+struct Saved {
+    allocations: Allocations,
+    deferred: Vec<DeferredOrder>,
+    #[serde(default)]
+    approved: HashSet<(String, ProblemInfo, OrderKind)>,
+}
+
+/// Planner bookkeeping shared with the HTTP endpoints.
+pub struct PlannerState {
+    pub allocations: Allocations,
+    /// Orders planned on the last cycle, before the limits and approvals
+    pub planned: Vec<PlannedOrderView>,
+    pub deferred: Vec<DeferredOrder>,
+    /// Orders an operator let through, by their planned key. Each approval
+    /// is spent when its order goes out
+    pub approved: HashSet<(String, ProblemInfo, OrderKind)>,
+    pub circuits: HashMap<String, CircuitBreaker>,
+    /// Time between two cycles
+    pub interval: Duration,
+    pub last_cycle: Option<CycleSummary>,
+}
+
+impl PlannerState {
+    pub fn new() -> Self {
+        Self::with_namespace(Uuid::new_v4())
+    }
+
+    /// Device uuids are derived from `namespace`, so the same namespace
+    /// hands out the same uuids.
+    pub fn with_namespace(namespace: Uuid) -> Self {
+        Self {
+            allocations: Allocations::new(namespace),
+            planned: Vec::new(),
+            deferred: Vec::new(),
+            approved: HashSet::new(),
+            circuits: HashMap::new(),
+            interval: Duration::seconds(120),
+            last_cycle: None,
+        }
+    }
+
+    /// The state saved in `path`, or a new one if there is no such file.
+    pub fn restore(path: Option<&Path>) -> Result<Self> {
+        let Some(path) = path.filter(|p| p.exists()) else {
+            return Ok(Self::new());
+        };
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Could not read planner state {}", path.display()))?;
+        let saved: Saved = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid planner state {}", path.display()))?;
+
+        info!(
+            "Restored {} pending additions from {}",
+            saved.allocations.count(AllocationStatus::Pending),
+            path.display()
+        );
+
+        Ok(Self {
+            allocations: saved.allocations,
+            deferred: saved.deferred,
+            approved: saved.approved,
+            ..Self::new()
+        })
+    }
+
+    /// Writes the allocations, deferred orders and approvals to `path`, replacing it
+    /// only once fully written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let saved = Saved {
+            allocations: self.allocations.clone(),
+            deferred: self.deferred.clone(),
+            approved: self.approved.clone(),
+        };
+        let tmp = path.with_extension("tmp");
+
+        fs::write(&tmp, serde_json::to_vec(&saved)?)
+            .with_context(|| format!("Could not write {}", tmp.display()))?;
+        fs::rename(&tmp, path).with_context(|| format!("Could not replace {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// Approves the orders of `app_name` waiting for it, only those for
+    /// `location` if given. Returns the approved orders.
+    pub fn approve(&mut self, app_name: &str, location: Option<&str>) -> Vec<DeferredOrder> {
+        let waiting = self
+            .deferred
+            .iter()
+            .filter(|d| d.app_name == app_name && d.reason == AWAITING_APPROVAL)
+            .filter(|d| location.is_none_or(|l| d.problem.location_key == l))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        self.approved.extend(waiting.iter().map(DeferredOrder::key));
+
+        waiting
+    }
+
+    /// Circuit of the dothing target at `url`.
+    pub fn circuit(&mut self, url: &str) -> &mut CircuitBreaker {
+        self.circuits
+            .entry(url.to_owned())
+            .or_insert_with(CircuitBreaker::new)
+    }
+
+    /// Whether a cycle finished within the last two intervals.
+    pub fn ran_recently(&self, now: DateTime<Utc>) -> bool {
+        let allowed = self.interval * 2 + Duration::seconds(STALL_GRACE_SECS);
+
+        self.last_cycle
+            .as_ref()
+            .is_some_and(|c| now - c.finished_at < allowed)
+    }
+
+    pub fn circuits_closed(&self) -> bool {
+        self.circuits.values().all(|c| !c.is_open())
+    }
+}
+
+impl Default for PlannerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
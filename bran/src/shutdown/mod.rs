@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+
+/// Tells every part of bran that it is time to stop.
+#[derive(Clone)]
+pub struct Shutdown {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Resolves once shutdown starts, right away if it already did.
+    pub async fn wait(&self) {
+        let mut receiver = self.sender.subscribe();
+        let _ = receiver.wait_for(|stopping| *stopping).await;
+    }
+
+    /// Starts the shutdown on SIGTERM or SIGINT. Each is listened for on
+    /// its own, so one that can't be still leaves the other.
+    pub async fn on_signal(self) {
+        let terminate = async {
+            match signal(SignalKind::terminate()) {
+                Ok(mut terminate) => {
+                    terminate.recv().await;
+                }
+                Err(e) => {
+                    error!("Could not listen for SIGTERM: {e}");
+                    std::future::pending::<()>().await;
+                }
+            }
+        };
+
+        let interrupt = async {
+            if let Err(e) = tokio::signal::ctrl_c().await {
+                error!("Could not listen for SIGINT: {e}");
+                std::future::pending::<()>().await;
+            }
+        };
+
+        tokio::select! {
+            _ = terminate => info!("SIGTERM received"),
+            _ = interrupt => info!("SIGINT received"),
+        }
+
+        self.trigger();
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,5 @@
+mod simulator;
+mod state_update;
+
+pub use simulator::{SimulatedOrder, Simulator};
+pub use state_update::{read_updates, StateUpdate};
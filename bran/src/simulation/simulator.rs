@@ -0,0 +1,139 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use super::StateUpdate;
+use crate::aggregator::{ApplicationRegister, ImportMode, RegisterSnapshot};
+use crate::config::{EventsConfig, PlannerConfig, RegisterConfig};
+use crate::events::EventBus;
+use crate::metrics::Metrics;
+use crate::planner::{Clock, Dispatch, Order, OrderKind, Planner, PlannerState, ProblemInfo};
+use crate::shutdown::Shutdown;
+
+/// An order the planner emitted during a simulation.
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulatedOrder {
+    pub at: DateTime<Utc>,
+    pub cycle: u64,
+    pub app: String,
+    pub problem: ProblemInfo,
+    pub kind: OrderKind,
+    pub order: Order,
+}
+
+/// Runs the planner over recorded state updates on a virtual clock. Every
+/// order is taken as delivered, nothing reaches dothing.
+pub struct Simulator {
+    register: Arc<Mutex<ApplicationRegister>>,
+    planner: Planner,
+    clock: Clock,
+    dispatch: Dispatch,
+    config: PlannerConfig,
+}
+
+impl Simulator {
+    /// Starts from the applications and directives in `snapshot`.
+    /// Applications need a dothing target, from the snapshot or `config`.
+    pub fn new(snapshot: RegisterSnapshot, config: PlannerConfig) -> Result<Self> {
+        if config.interval_secs == 0 {
+            bail!("planner.interval_secs must be at least 1");
+        }
+
+        let mut register = ApplicationRegister::new(&RegisterConfig::default());
+        register.import(snapshot, ImportMode::Replace)?;
+        let register = Arc::new(Mutex::new(register));
+
+        let clock = Clock::starting_at(DateTime::UNIX_EPOCH);
+        let dispatch = Dispatch::record();
+        let (_, updates) = watch::channel(config.clone());
+
+        // A fixed namespace, so every run hands out the same device uuids
+        let planner = Planner::new(
+            Arc::clone(&register),
+            Arc::new(Mutex::new(PlannerState::with_namespace(Uuid::nil()))),
+            Arc::new(EventBus::new(&EventsConfig::default())),
+            Arc::new(Metrics::new()?),
+            updates,
+            Shutdown::new(),
+        )
+        .with_clock(clock.clone())
+        .with_dispatch(dispatch.clone());
+
+        Ok(Self {
+            register,
+            planner,
+            clock,
+            dispatch,
+            config,
+        })
+    }
+
+    /// Replays `updates` and returns the orders of every cycle, the first
+    /// one `delay_secs` after the first update. Without `until` the cycles
+    /// go on for an addition timeout past the last update, so additions
+    /// that never show up are caught.
+    pub async fn run(
+        mut self,
+        updates: Vec<StateUpdate>,
+        until: Option<DateTime<Utc>>,
+    ) -> Vec<SimulatedOrder> {
+        let Some(first) = updates.first().map(|u| u.at) else {
+            return Vec::new();
+        };
+
+        let interval = Duration::seconds(self.config.interval_secs as i64);
+        let until = until.unwrap_or_else(|| {
+            let last = updates.last().map_or(first, |u| u.at);
+            last + Duration::seconds(self.config.addition_timeout_secs as i64) + interval
+        });
+
+        let mut updates = updates.into_iter().peekable();
+        let mut timeline = Vec::new();
+        let mut now = first + Duration::seconds(self.config.delay_secs as i64);
+        let mut cycle = 0;
+
+        while now <= until {
+            cycle += 1;
+            self.clock.set(now);
+
+            {
+                let mut register = self.register.lock().unwrap();
+
+                while let Some(update) = updates.next_if(|u| u.at <= now) {
+                    if !register.specs.contains_key(&update.app) {
+                        warn!(
+                            "Skipping update of {}, it is not in the snapshot",
+                            update.app
+                        );
+                        continue;
+                    }
+
+                    register.statuses.insert(update.app, update.state);
+                }
+            }
+
+            self.planner.step().await;
+
+            // Orders are recorded as their tasks finish
+            let mut sent = self.dispatch.take();
+            sent.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+
+            timeline.extend(sent.into_iter().map(|planned| SimulatedOrder {
+                at: now,
+                cycle,
+                app: planned.app_name,
+                problem: planned.problem,
+                kind: planned.order.kind(),
+                order: planned.order,
+            }));
+
+            now += interval;
+        }
+
+        timeline
+    }
+}
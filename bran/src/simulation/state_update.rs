@@ -0,0 +1,36 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use starduck::Application;
+
+/// A state report as a monitor sent it, at the time it arrived.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateUpdate {
+    pub at: DateTime<Utc>,
+    pub app: String,
+    pub state: Application,
+}
+
+/// Reads one update per line, oldest first. Blank lines are skipped.
+pub fn read_updates(path: &Path) -> Result<Vec<StateUpdate>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Could not read updates {}", path.display()))?;
+
+    let mut updates = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str::<StateUpdate>(line)
+                .with_context(|| format!("Invalid update on line {} of {}", i + 1, path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    updates.sort_by_key(|update| update.at);
+
+    Ok(updates)
+}
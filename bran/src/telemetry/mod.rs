@@ -0,0 +1,7 @@
+mod request_span;
+mod subscriber;
+
+pub use request_span::{make_request_span, REQUEST_ID};
+#[cfg(feature = "otlp")]
+pub use subscriber::otlp;
+pub use subscriber::{init_stderr, LogLevel, Telemetry};
@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Result};
+use tracing::Subscriber;
+use tracing_subscriber::{fmt, layer::Layered, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{reload, EnvFilter, Layer, Registry};
+
+use crate::config::{LogFormat, LoggingConfig};
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+type FilterHandle = reload::Handle<EnvFilter, Layered<Vec<BoxedLayer>, Registry>>;
+
+/// Changes the log level of the running subscriber.
+#[derive(Clone)]
+pub struct LogLevel(FilterHandle);
+
+impl LogLevel {
+    pub fn set(&self, level: &str) -> Result<()> {
+        self.0.reload(EnvFilter::try_new(level)?)?;
+        Ok(())
+    }
+
+    /// The filter in use, in the `RUST_LOG` syntax.
+    pub fn current(&self) -> Option<String> {
+        self.0.with_current(|filter| filter.to_string()).ok()
+    }
+}
+
+/// Keeps the exporters alive. Dropping it flushes the pending spans.
+pub struct Telemetry {
+    log_level: LogLevel,
+    #[cfg(feature = "otlp")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Telemetry {
+    /// Installs the global subscriber. The JSON format writes one object
+    /// per line.
+    pub fn init(config: &LoggingConfig) -> Result<Self> {
+        let (subscriber, telemetry) = Self::build(config)?;
+        subscriber.try_init()?;
+
+        Ok(telemetry)
+    }
+
+    /// The subscriber `init` installs, left to the caller to install.
+    pub fn build(
+        config: &LoggingConfig,
+    ) -> Result<(impl Subscriber + Send + Sync + 'static, Self)> {
+        let mut layers: Vec<BoxedLayer> = Vec::new();
+
+        let format = match config.format {
+            LogFormat::Json => fmt::layer().json().with_current_span(true).boxed(),
+            LogFormat::Text => fmt::layer().boxed(),
+        };
+        layers.push(format);
+
+        #[cfg(feature = "otlp")]
+        let provider = match &config.otlp_endpoint {
+            Some(endpoint) => {
+                let (layer, provider) = otlp::layer(endpoint)?;
+                layers.push(layer);
+                Some(provider)
+            }
+            None => None,
+        };
+
+        let (filter, handle) = reload::Layer::new(EnvFilter::try_new(&config.level)?);
+
+        let subscriber = tracing_subscriber::registry().with(layers).with(filter);
+
+        let telemetry = Self {
+            log_level: LogLevel(handle),
+            #[cfg(feature = "otlp")]
+            provider,
+        };
+
+        Ok((subscriber, telemetry))
+    }
+}
+
+/// Plain logs on stderr for the one-shot commands, which print their
+/// results on stdout.
+pub fn init_stderr(level: &str) -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(EnvFilter::try_new(level)?)
+        .try_init()
+        .map_err(|e| anyhow!("{e}"))
+}
+
+impl Telemetry {
+    pub fn log_level(&self) -> LogLevel {
+        self.log_level.clone()
+    }
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Could not flush OTLP spans: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "otlp")]
+pub mod otlp {
+    use anyhow::Result;
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::trace::{SdkTracerProvider, SpanExporter};
+    use opentelemetry_sdk::Resource;
+    use tracing_subscriber::Layer;
+
+    use super::BoxedLayer;
+
+    /// Exports spans over OTLP/gRPC to `endpoint`.
+    pub fn layer(endpoint: &str) -> Result<(BoxedLayer, SdkTracerProvider)> {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()?;
+
+        Ok(exporting_to(exporter))
+    }
+
+    /// Sends the spans, as the `bran` service, to `exporter` in batches.
+    pub fn exporting_to(exporter: impl SpanExporter + 'static) -> (BoxedLayer, SdkTracerProvider) {
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(Resource::builder().with_service_name("bran").build())
+            .build();
+
+        let layer = tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer("bran"))
+            .boxed();
+
+        (layer, provider)
+    }
+}
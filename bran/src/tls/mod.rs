@@ -0,0 +1,5 @@
+mod reload;
+mod tls_settings;
+
+pub use reload::reload_on_sighup;
+pub use tls_settings::TlsSettings;
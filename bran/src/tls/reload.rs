@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use axum_server::tls_rustls::RustlsConfig;
+use tokio::signal::unix::{signal, SignalKind};
+
+use super::TlsSettings;
+
+/// Reloads the certificates every time bran gets a SIGHUP. A failed reload
+/// keeps serving with the previous certificates.
+pub async fn reload_on_sighup(config: RustlsConfig, settings: TlsSettings) {
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(k) => k,
+        Err(e) => {
+            error!("Could not listen for SIGHUP, certificates won't be reloaded: {e}");
+            return;
+        }
+    };
+
+    while hangup.recv().await.is_some() {
+        info!("SIGHUP received. Reloading TLS certificates");
+
+        match settings.server_config() {
+            Ok(server_config) => {
+                config.reload_from_config(Arc::new(server_config));
+                info!("TLS certificates reloaded");
+            }
+            Err(e) => error!("Could not reload TLS certificates, keeping the old ones: {e}"),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+
+use crate::config::TlsConfig;
+
+/// Certificates used to terminate TLS. With `client_ca` set, clients must
+/// present a certificate signed by it.
+#[derive(Debug, Clone)]
+pub struct TlsSettings {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    pub client_ca: Option<PathBuf>,
+}
+
+impl TlsSettings {
+    /// Returns `None` when TLS is not configured.
+    pub fn from_config(config: &TlsConfig) -> Result<Option<Self>> {
+        match (&config.cert, &config.key) {
+            (Some(cert), Some(key)) => Ok(Some(Self {
+                cert: cert.clone(),
+                key: key.clone(),
+                client_ca: config.client_ca.clone(),
+            })),
+            (None, None) if config.client_ca.is_none() => Ok(None),
+            _ => bail!("TLS needs both a certificate and a key"),
+        }
+    }
+
+    /// Reads the certificates from disk and builds the rustls config.
+    pub fn server_config(&self) -> Result<ServerConfig> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+        let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+            .with_safe_default_protocol_versions()?;
+
+        let builder = match &self.client_ca {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in load_certs(path)? {
+                    roots.add(cert)?;
+                }
+
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                        .build()
+                        .context("Could not build client certificate verifier")?;
+
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let mut config = builder.with_single_cert(load_certs(&self.cert)?, load_key(&self.key)?)?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        Ok(config)
+    }
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let file = File::open(path).with_context(|| format!("Could not open {}", path.display()))?;
+
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Could not parse certificates in {}", path.display()))?;
+
+    if certs.is_empty() {
+        bail!("No certificates found in {}", path.display());
+    }
+
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    let file = File::open(path).with_context(|| format!("Could not open {}", path.display()))?;
+
+    match rustls_pemfile::private_key(&mut BufReader::new(file))? {
+        Some(key) => Ok(key),
+        None => bail!("No private key found in {}", path.display()),
+    }
+}
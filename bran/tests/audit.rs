@@ -0,0 +1,136 @@
+mod common;
+
+use futures_util::future::join_all;
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use common::{addition, spec, MockDothing, TestBran};
+
+/// The audit entries matching `query`, oldest first.
+async fn entries(bran: &TestBran, query: &str) -> Vec<Value> {
+    let (status, body) = bran.get(&format!("/audit{query}")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    body.as_array().unwrap().clone()
+}
+
+/// A `farm` spec with a copy of location `l1` under every name in `locations`.
+fn farm_at<S: AsRef<str>>(locations: &[S]) -> Value {
+    let mut farm = spec("farm", 1);
+    let l1 = farm["locations"]["locations"]["l1"].clone();
+
+    for location in locations {
+        let location = location.as_ref();
+        let mut copy = l1.clone();
+        copy["name"] = json!(location);
+        farm["locations"]["locations"][location] = copy;
+    }
+
+    farm
+}
+
+fn paths(entry: &Value) -> Vec<&str> {
+    entry["changes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["path"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn mutations_are_recorded_with_what_they_changed() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    bran.register(&spec("farm", 1)).await;
+    bran.direct("addition", "farm", "l1", &addition()).await;
+
+    let (status, _) = bran
+        .send(Method::POST, "/apps/farm/rollback/99", &Value::Null)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Reads are not audited
+    bran.get("/apps/farm").await;
+
+    let entries = entries(&bran, "").await;
+    assert_eq!(entries.len(), 3);
+
+    let [registered, directed, refused] = &entries[..] else {
+        unreachable!()
+    };
+
+    assert_eq!(registered["method"], "POST");
+    assert_eq!(registered["path"], "/apps/farm");
+    assert_eq!(registered["app"], "farm");
+    assert_eq!(registered["status"], 200);
+    assert!(paths(registered).contains(&"/application"));
+
+    assert_eq!(directed["path"], "/directives/addition/farm/l1");
+    assert_eq!(directed["location"], "l1");
+    assert!(paths(directed).iter().all(|p| p.starts_with("/directives")));
+    assert!(!paths(directed).is_empty());
+
+    assert_eq!(refused["status"], 404);
+    assert_eq!(refused["changes"], json!([]));
+}
+
+#[tokio::test]
+async fn entries_are_filtered_by_app_location_and_limit() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    bran.register(&farm_at(&["l2"])).await;
+    bran.register(&spec("barn", 1)).await;
+    bran.direct("addition", "farm", "l1", &addition()).await;
+    bran.direct("addition", "farm", "l2", &addition()).await;
+
+    assert_eq!(entries(&bran, "?app=barn").await.len(), 1);
+    assert_eq!(entries(&bran, "?app=farm").await.len(), 3);
+
+    let located = entries(&bran, "?app=farm&location=l2").await;
+    assert_eq!(located.len(), 1);
+    assert_eq!(located[0]["location"], "l2");
+
+    let last = entries(&bran, "?limit=1").await;
+    assert_eq!(last.len(), 1);
+    assert_eq!(last[0]["location"], "l2");
+
+    let since = entries(&bran, "?since=2100-01-01T00:00:00Z").await;
+    assert!(since.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_mutations_only_record_their_own_changes() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    let locations: Vec<_> = (2..34).map(|i| format!("l{i}")).collect();
+    bran.register(&farm_at(&locations)).await;
+    bran.direct("addition", "farm", "l1", &addition()).await;
+
+    let order = addition();
+    join_all(
+        locations
+            .iter()
+            .map(|location| bran.direct("addition", "farm", location, &order)),
+    )
+    .await;
+
+    let entries = entries(&bran, "?app=farm").await;
+    assert_eq!(entries.len(), locations.len() + 2);
+
+    for entry in entries.iter().skip(2) {
+        let location = entry["location"].as_str().unwrap();
+        let changed = paths(entry);
+
+        assert!(!changed.is_empty());
+        assert!(
+            changed
+                .iter()
+                .all(|p| p.starts_with(&format!("/directives/{location}"))),
+            "{location} recorded {changed:?}"
+        );
+    }
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use std::path::PathBuf;
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use bran::auth::Authenticator;
+
+use common::{addition, report, spec, MockDothing, TestBran};
+
+const REPORTER: &str = "reporter-token";
+const OPERATOR: &str = "operator-token";
+const ADMIN: &str = "admin-token";
+
+/// A tokens file with one token per role, removed when dropped.
+struct TokensFile(PathBuf);
+
+impl TokensFile {
+    fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("bran-tokens-{}.json", Uuid::new_v4()));
+        let tokens = json!([
+            {"name": "monitor", "token": REPORTER, "role": "reporter"},
+            {"name": "ops", "token": OPERATOR, "role": "operator"},
+            {"name": "root", "token": ADMIN, "role": "admin"},
+        ]);
+        std::fs::write(&path, tokens.to_string()).unwrap();
+
+        Self(path)
+    }
+}
+
+impl Drop for TokensFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+async fn start() -> (MockDothing, TestBran) {
+    let tokens = TokensFile::new();
+    let dothing = MockDothing::start().await;
+    let authenticator = Authenticator::from_file(&tokens.0).unwrap();
+    let bran = TestBran::start_with_auth(&dothing, authenticator).await;
+
+    (dothing, bran)
+}
+
+async fn status_as(bran: &TestBran, token: Option<&str>, method: Method, path: &str) -> StatusCode {
+    bran.send_as(token, method, path, &Value::Null).await.0
+}
+
+#[tokio::test]
+async fn requests_without_a_known_token_are_refused() {
+    let (_dothing, bran) = start().await;
+
+    assert_eq!(
+        status_as(&bran, None, Method::GET, "/apps").await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        status_as(&bran, Some("guess"), Method::GET, "/apps").await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        status_as(&bran, Some(OPERATOR), Method::GET, "/apps").await,
+        StatusCode::OK
+    );
+
+    // Probes stay open
+    assert_eq!(
+        status_as(&bran, None, Method::GET, "/healthz").await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn each_role_gets_what_it_is_for() {
+    let (_dothing, bran) = start().await;
+
+    let (status, body) = bran
+        .send_as(Some(OPERATOR), Method::POST, "/apps/farm", &spec("farm", 1))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    // Reporters only report state
+    let (status, body) = bran
+        .send_as(
+            Some(REPORTER),
+            Method::PUT,
+            "/apps/farm",
+            &report("farm", 1, &[]),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    for path in ["/apps/farm", "/audit", "/events", "/planner/deferred"] {
+        assert_eq!(
+            status_as(&bran, Some(REPORTER), Method::GET, path).await,
+            StatusCode::FORBIDDEN,
+            "{path}"
+        );
+    }
+
+    let (status, _) = bran
+        .send_as(
+            Some(REPORTER),
+            Method::POST,
+            "/directives/addition/farm/l1",
+            &addition(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Operators read and direct, but don't delete
+    assert_eq!(
+        status_as(&bran, Some(OPERATOR), Method::GET, "/apps/farm").await,
+        StatusCode::OK
+    );
+    let (status, body) = bran
+        .send_as(
+            Some(OPERATOR),
+            Method::POST,
+            "/directives/addition/farm/l1",
+            &addition(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let allocation = format!("/planner/allocations/farm/{}", Uuid::new_v4());
+    assert_eq!(
+        status_as(&bran, Some(OPERATOR), Method::DELETE, &allocation).await,
+        StatusCode::FORBIDDEN
+    );
+
+    // Admins get to the handler, which has nothing to clear
+    assert_eq!(
+        status_as(&bran, Some(ADMIN), Method::DELETE, &allocation).await,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn denied_requests_are_audited() {
+    let (_dothing, bran) = start().await;
+
+    status_as(&bran, None, Method::POST, "/apps/farm").await;
+    status_as(&bran, Some(REPORTER), Method::GET, "/audit").await;
+
+    let (status, entries) = bran
+        .send_as(Some(OPERATOR), Method::GET, "/audit", &Value::Null)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{entries}");
+
+    let entries = entries.as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+
+    assert_eq!(entries[0]["status"], 401);
+    assert_eq!(entries[0]["method"], "POST");
+    assert_eq!(entries[0]["path"], "/apps/farm");
+    assert_eq!(entries[0]["identity"], Value::Null);
+    assert_eq!(
+        entries[0]["client"].as_str().unwrap().split(':').next(),
+        Some("127.0.0.1")
+    );
+
+    assert_eq!(entries[1]["status"], 403);
+    assert_eq!(entries[1]["path"], "/audit");
+    assert_eq!(entries[1]["identity"], "monitor");
+    assert_eq!(entries[1]["role"], "reporter");
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use common::{addition, report, spec};
+
+/// A file of its own in the temp dir, removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn with(content: &Value) -> Self {
+        let path = std::env::temp_dir().join(format!("bran-cli-{}.json", Uuid::new_v4()));
+        std::fs::write(&path, content.to_string()).unwrap();
+
+        Self(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn bran(args: &[&str], file: &TempFile) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_bran"))
+        .args(args)
+        .arg(&file.0)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+/// A snapshot of `farm` missing its one sensor, with `order` as the
+/// addition directive of `l1`.
+fn missing_sensor(order: Value) -> Value {
+    json!({
+        "version": 1,
+        "exported_at": "2024-01-01T00:00:00Z",
+        "apps": {
+            "farm": {
+                "application": spec("farm", 1),
+                "status": report("farm", 1, &[]),
+                "directives": {"l1": {"addition": order}},
+                "target": {"url": "http://127.0.0.1:9"},
+            },
+        },
+    })
+}
+
+#[test]
+fn plan_prints_the_built_orders() {
+    let snapshot = TempFile::with(&missing_sensor(addition()));
+
+    let first = bran(&["plan"], &snapshot);
+    assert!(first.status.success(), "{first:?}");
+
+    let orders: Value = serde_json::from_str(&stdout(&first)).unwrap();
+    assert_eq!(orders.as_array().unwrap().len(), 1);
+
+    let order = &orders[0];
+    assert_eq!(order["app"], "farm");
+    assert_eq!(order["kind"], "Addition");
+
+    let device_uuid = order["problem"]["device_uuid"].as_str().unwrap();
+    assert_eq!(order["order"]["env_vars"]["device_uuid"], device_uuid);
+    assert!(order["order"]["args"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("location:l1")));
+
+    // The same uuids on every run
+    let second = bran(&["plan"], &snapshot);
+    assert_eq!(stdout(&first), stdout(&second));
+}
+
+#[test]
+fn plan_fails_on_orders_that_cant_be_built() {
+    let mut order = addition();
+    order["args"] = json!(["key:a", "key:b"]);
+    let snapshot = TempFile::with(&missing_sensor(order));
+
+    let output = bran(&["plan"], &snapshot);
+    assert!(!output.status.success());
+
+    let orders: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert!(orders[0]["error"].as_str().unwrap().contains("key"));
+    assert!(orders[0].get("order").is_none());
+}
+
+#[test]
+fn validate_reports_what_is_wrong() {
+    let app = TempFile::with(&spec("farm", 1));
+    let output = bran(&["validate"], &app);
+    assert!(output.status.success(), "{output:?}");
+    assert!(stdout(&output).contains("valid app file"));
+
+    let mut broken = spec("farm", 1);
+    broken["locations"]["locations"]["l1"]["locations"]["l2"] =
+        broken["locations"]["locations"]["l1"].clone();
+    let app = TempFile::with(&broken);
+
+    let output = bran(&["validate", "--kind", "app"], &app);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("Location l1 has both sublocations and data requirements"));
+
+    let snapshot = TempFile::with(&missing_sensor(addition()));
+    let output = bran(&["validate"], &snapshot);
+    assert!(output.status.success(), "{output:?}");
+    assert!(stdout(&output).contains("valid snapshot file"));
+
+    let mut unknown = missing_sensor(addition());
+    unknown["apps"]["farm"]["directives"]["l9"] = json!({"addition": addition()});
+    let snapshot = TempFile::with(&unknown);
+
+    let output = bran(&["validate"], &snapshot);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("App farm has directives for unknown location l9"));
+
+    // Without a default target, every app needs one of its own
+    let mut untargeted = missing_sensor(addition());
+    untargeted["apps"]["farm"]
+        .as_object_mut()
+        .unwrap()
+        .remove("target");
+    let snapshot = TempFile::with(&untargeted);
+
+    let output = bran(&["validate"], &snapshot);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("App farm has no dothing target and there is no default"));
+
+    let output = bran(&["--dothing", "http://127.0.0.1:9", "validate"], &snapshot);
+    assert!(output.status.success(), "{output:?}");
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use uuid::Uuid;
+
+use bran_client::{AuditQuery, BranClient, Error, ImportMode, OrderKind};
+
+use common::{addition, report, restart, spec, MockDothing, TestBran};
+
+fn typed<T: DeserializeOwned>(value: Value) -> T {
+    serde_json::from_value(value).unwrap()
+}
+
+/// Goes through the API with the client only, so every answer has to
+/// decode into the client's copy of the server types.
+#[tokio::test]
+async fn client_types_match_what_bran_answers() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+    bran.configure(|c| c.require_approval = true);
+    let client = BranClient::new(&bran.url).unwrap();
+    let sensor = Uuid::new_v4();
+
+    client
+        .register_app("farm", &typed(spec("farm", 2)))
+        .await
+        .unwrap();
+    client
+        .set_addition("farm", "l1", &typed(addition()))
+        .await
+        .unwrap();
+    client
+        .set_restart("farm", "l1", &typed(restart()))
+        .await
+        .unwrap();
+    client
+        .report_state("farm", &typed(report("farm", 2, &[(sensor, "Fault")])))
+        .await
+        .unwrap();
+
+    let apps = client.list_apps().await.unwrap();
+    assert_eq!(apps[0].name, "farm");
+    assert_eq!(client.get_app("farm").await.unwrap().name, "farm");
+    assert_eq!(client.get_app_status("farm").await.unwrap().name, "farm");
+    assert!(client
+        .get_directives("farm")
+        .await
+        .unwrap()
+        .contains_key("l1"));
+
+    let versions = client.list_versions("farm").await.unwrap();
+    let last = versions.last().unwrap().version;
+    assert_eq!(
+        client.get_version("farm", last).await.unwrap().version,
+        last
+    );
+    assert!(!client
+        .diff_versions("farm", 1, last)
+        .await
+        .unwrap()
+        .is_empty());
+
+    bran.step().await;
+
+    let planned = client.get_planned_orders().await.unwrap();
+    assert!(planned.iter().any(|p| p.kind == OrderKind::Addition));
+
+    let deferred = client.get_deferred_orders().await.unwrap();
+    assert!(deferred.iter().any(|d| d.kind == OrderKind::Addition));
+
+    let approval = client.approve_orders("farm", Some("l1")).await.unwrap();
+    assert_eq!(approval.approved.len(), deferred.len());
+
+    bran.step().await;
+
+    let allocations = client.get_allocations("farm").await.unwrap();
+    assert_eq!(allocations.len(), 1);
+    assert!(client.get_circuits().await.unwrap().values().count() <= 1);
+
+    let status = client.status().await.unwrap();
+    assert_eq!(status.apps.registered, 1);
+    assert_eq!(status.allocations.pending, 1);
+
+    let snapshot = client.export_register(false).await.unwrap();
+    let imported = client
+        .import_register(&snapshot, ImportMode::Merge, true)
+        .await
+        .unwrap();
+    assert!(imported.dry_run);
+
+    let entries = client
+        .get_audit_entries(&AuditQuery {
+            app: Some("farm".to_owned()),
+            ..AuditQuery::default()
+        })
+        .await
+        .unwrap();
+    assert!(!entries.is_empty());
+
+    client.health().await.unwrap();
+    client.readiness().await.unwrap();
+    client.version().await.unwrap();
+    assert!(client
+        .metrics()
+        .await
+        .unwrap()
+        .contains("bran_orders_total"));
+
+    assert!(matches!(
+        client.get_app("ghost").await,
+        Err(Error::NotFound(_))
+    ));
+}
+
+#[test]
+fn only_http_urls_make_a_client() {
+    assert!(BranClient::new("https://bran.example:8014/").is_ok());
+
+    for url in [
+        "ftp://bran.example",
+        "mailto:ops@bran.example",
+        "unix:/run/bran",
+    ] {
+        assert!(
+            matches!(BranClient::new(url), Err(Error::NotHttp(_))),
+            "{url}"
+        );
+    }
+    assert!(matches!(BranClient::new("bran"), Err(Error::Url(_))));
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{
+    extract::State,
+    http::{StatusCode, Uri},
+    routing::post,
+    Json, Router,
+};
+use serde_json::Value;
+use tokio::net::TcpListener;
+use url::Url;
+
+/// An order received by the mock dothing.
+#[derive(Debug, Clone)]
+pub struct Call {
+    pub endpoint: String,
+    pub body: Value,
+}
+
+type Calls = Arc<Mutex<Vec<Call>>>;
+type Answers = Arc<Mutex<HashMap<String, StatusCode>>>;
+type Delay = Arc<Mutex<Duration>>;
+
+#[derive(Clone, Default)]
+struct Mock {
+    calls: Calls,
+    answers: Answers,
+    delay: Delay,
+}
+
+/// Stands in for dothing, keeping every order for the test to look at.
+/// Orders are accepted unless told otherwise with [`MockDothing::answer`].
+pub struct MockDothing {
+    pub url: Url,
+    calls: Calls,
+    answers: Answers,
+    delay: Delay,
+}
+
+impl MockDothing {
+    pub async fn start() -> Self {
+        let mock = Mock::default();
+
+        let app = Router::new()
+            .route("/addition", post(record))
+            .route("/restart", post(record))
+            .route("/reconfig/http", post(record))
+            .with_state(mock.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        Self {
+            url: Url::parse(&format!("http://{addr}")).unwrap(),
+            calls: mock.calls,
+            answers: mock.answers,
+            delay: mock.delay,
+        }
+    }
+
+    /// Answers every order sent to `endpoint` from now on with `status`.
+    /// The orders are still recorded.
+    pub fn answer(&self, endpoint: &str, status: StatusCode) {
+        self.answers
+            .lock()
+            .unwrap()
+            .insert(endpoint.to_owned(), status);
+    }
+
+    /// Takes `delay` to answer every order from now on. Orders are recorded
+    /// as soon as they arrive.
+    pub fn delay(&self, delay: Duration) {
+        *self.delay.lock().unwrap() = delay;
+    }
+
+    /// Orders received so far, oldest first.
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Endpoints of the orders received since the last take, which are
+    /// forgotten.
+    pub fn take_endpoints(&self) -> Vec<String> {
+        self.calls
+            .lock()
+            .unwrap()
+            .drain(..)
+            .map(|call| call.endpoint)
+            .collect()
+    }
+}
+
+async fn record(State(mock): State<Mock>, uri: Uri, Json(body): Json<Value>) -> StatusCode {
+    let endpoint = uri.path().to_owned();
+    let status = mock.answers.lock().unwrap().get(&endpoint).copied();
+
+    mock.calls.lock().unwrap().push(Call { endpoint, body });
+
+    let delay = *mock.delay.lock().unwrap();
+    tokio::time::sleep(delay).await;
+
+    status.unwrap_or(StatusCode::OK)
+}
@@ -0,0 +1,102 @@
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// An app with one location, `l1`, asking for `count` temperature sensors.
+pub fn spec(app: &str, count: usize) -> Value {
+    application(app, "Uninitialized", count, "Uninitialized", Vec::new())
+}
+
+/// What the monitor of an app built by [`spec`] reports, with `sensors`
+/// as the uuid and status of every sensor it sees.
+pub fn report(app: &str, count: usize, sensors: &[(Uuid, &str)]) -> Value {
+    let healthy = sensors.len() >= count && sensors.iter().all(|(_, s)| *s == "Coherent");
+    let status = if healthy { "Coherent" } else { "Degraded" };
+
+    let components = sensors
+        .iter()
+        .enumerate()
+        .map(|(i, (uuid, status))| {
+            json!({
+                "name": format!("sensor-{i}"),
+                "uuid": uuid,
+                "status": status,
+                "last_reading": null,
+            })
+        })
+        .collect();
+
+    application(app, status, count, status, components)
+}
+
+fn application(
+    app: &str,
+    status: &str,
+    count: usize,
+    data_status: &str,
+    components: Vec<Value>,
+) -> Value {
+    json!({
+        "name": app,
+        "description": null,
+        "status": status,
+        "last_update": null,
+        "locations": {
+            "name": "root",
+            "ip": null,
+            "status": status,
+            "data_requirements": {},
+            "properties": {},
+            "locations": {
+                "l1": {
+                    "name": "l1",
+                    "ip": null,
+                    "status": status,
+                    "locations": {},
+                    "properties": {},
+                    "data_requirements": {
+                        "temp": {
+                            "components": components,
+                            "required": true,
+                            "count": count,
+                            "timeout": null,
+                            "status": data_status,
+                            "output": "Number",
+                        },
+                    },
+                },
+            },
+        },
+    })
+}
+
+pub fn addition() -> Value {
+    json!({
+        "image": "sensor:1",
+        "network_name": "iot",
+        "env_vars": {},
+        "args": [],
+    })
+}
+
+pub fn restart() -> Value {
+    json!({
+        "uuid": null,
+        "query_type": {"Http": {"endpoint": "/health", "port": 8080}},
+    })
+}
+
+pub fn reconfig() -> Value {
+    json!({
+        "uuid": null,
+        "network": "iot",
+        "query_type": {"Http": {"endpoint": "/health", "port": 8080}},
+        "reconfig": {
+            "Http": {
+                "endpoint": "/config",
+                "port": 8080,
+                "method": "POST",
+                "payload": {"interval": 5},
+            },
+        },
+    })
+}
@@ -0,0 +1,215 @@
+// Each test binary uses its own part of the harness
+#![allow(dead_code)]
+
+mod dothing;
+mod fixtures;
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use bran::aggregator::ApplicationRegister;
+use bran::audit::AuditLog;
+use bran::auth::Authenticator;
+use bran::config::{EventsConfig, PlannerConfig, RegisterConfig};
+use bran::endpoints::{self, Services};
+use bran::events::EventBus;
+use bran::journal::Journal;
+use bran::metrics::Metrics;
+use bran::planner::{Planner, PlannerState};
+use bran::shutdown::Shutdown;
+
+pub use dothing::MockDothing;
+pub use fixtures::*;
+
+/// A bran serving its API on a local port, with an in-memory register and
+/// a planner that only runs when stepped.
+pub struct TestBran {
+    pub url: String,
+    pub register: Arc<Mutex<ApplicationRegister>>,
+    pub planner_state: Arc<Mutex<PlannerState>>,
+    pub events: Arc<EventBus>,
+    planner: Planner,
+    planner_config: watch::Sender<PlannerConfig>,
+    http: reqwest::Client,
+    shutdown: Shutdown,
+    audit_path: PathBuf,
+}
+
+impl TestBran {
+    /// Starts bran with `dothing` as the default target of every app.
+    pub async fn start(dothing: &MockDothing) -> Self {
+        Self::start_with_journal(dothing, None).await
+    }
+
+    /// Same as `start`, recording request bodies to `journal`.
+    pub async fn start_with_journal(dothing: &MockDothing, journal: Option<Arc<Journal>>) -> Self {
+        Self::launch(dothing, journal, Authenticator::disabled()).await
+    }
+
+    /// Same as `start`, checking API tokens with `authenticator`.
+    pub async fn start_with_auth(dothing: &MockDothing, authenticator: Authenticator) -> Self {
+        Self::launch(dothing, None, authenticator).await
+    }
+
+    async fn launch(
+        dothing: &MockDothing,
+        journal: Option<Arc<Journal>>,
+        authenticator: Authenticator,
+    ) -> Self {
+        let register = Arc::new(Mutex::new(ApplicationRegister::new(
+            &RegisterConfig::default(),
+        )));
+        let planner_state = Arc::new(Mutex::new(PlannerState::new()));
+        let events = Arc::new(EventBus::new(&EventsConfig::default()));
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let shutdown = Shutdown::new();
+
+        let audit_path = std::env::temp_dir().join(format!("bran-audit-{}.log", Uuid::new_v4()));
+        let audit_log = AuditLog::open(audit_path.clone()).unwrap();
+
+        let app = endpoints::router(Services {
+            register: Arc::clone(&register),
+            planner_state: Arc::clone(&planner_state),
+            events: Arc::clone(&events),
+            metrics: Arc::clone(&metrics),
+            authenticator: Arc::new(authenticator),
+            audit_log: Arc::new(audit_log),
+            journal,
+            shutdown: shutdown.clone(),
+        });
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stopped = shutdown.clone();
+
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(async move { stopped.wait().await })
+            .await
+            .unwrap();
+        });
+
+        let config = PlannerConfig {
+            dothing: Some(dothing.url.clone()),
+            ..PlannerConfig::default()
+        };
+        let (planner_config, updates) = watch::channel(config);
+
+        let planner = Planner::new(
+            Arc::clone(&register),
+            Arc::clone(&planner_state),
+            Arc::clone(&events),
+            metrics,
+            updates,
+            shutdown.clone(),
+        );
+
+        Self {
+            url: format!("http://{addr}"),
+            register,
+            planner_state,
+            events,
+            planner,
+            planner_config,
+            http: reqwest::Client::new(),
+            shutdown,
+            audit_path,
+        }
+    }
+
+    /// Changes the planner settings, which apply from the next step.
+    pub fn configure(&self, change: impl FnOnce(&mut PlannerConfig)) {
+        self.planner_config.send_modify(change);
+    }
+
+    /// Runs one planner cycle, returning how many orders went out.
+    pub async fn step(&mut self) -> usize {
+        self.planner.step().await
+    }
+
+    /// Sends `body` as JSON, returning the status and the JSON answer, or
+    /// `Null` when there is none.
+    pub async fn send(&self, method: Method, path: &str, body: &Value) -> (StatusCode, Value) {
+        self.send_as(None, method, path, body).await
+    }
+
+    /// Same as `send`, with `token` as the API token.
+    pub async fn send_as(
+        &self,
+        token: Option<&str>,
+        method: Method,
+        path: &str,
+        body: &Value,
+    ) -> (StatusCode, Value) {
+        let mut request = self
+            .http
+            .request(method, format!("{}{}", self.url, path))
+            .json(body);
+
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await.unwrap();
+
+        let status = response.status();
+        let body = response.json().await.unwrap_or(Value::Null);
+
+        (status, body)
+    }
+
+    pub async fn get(&self, path: &str) -> (StatusCode, Value) {
+        let response = self
+            .http
+            .get(format!("{}{}", self.url, path))
+            .send()
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let body = response.json().await.unwrap_or(Value::Null);
+
+        (status, body)
+    }
+
+    /// Registers `spec` under its name, panicking if bran refuses it.
+    pub async fn register(&self, spec: &Value) {
+        let path = format!("/apps/{}", spec["name"].as_str().unwrap());
+        let (status, body) = self.send(Method::POST, &path, spec).await;
+
+        assert_eq!(status, StatusCode::OK, "{body}");
+    }
+
+    /// Sets a directive of `kind` (addition, reconfig or restart).
+    pub async fn direct(&self, kind: &str, app: &str, location: &str, order: &Value) {
+        let path = format!("/directives/{kind}/{app}/{location}");
+        let (status, body) = self.send(Method::POST, &path, order).await;
+
+        assert_eq!(status, StatusCode::OK, "{body}");
+    }
+
+    /// Reports the observed state of an app, as its monitor would.
+    pub async fn report(&self, state: &Value) {
+        let path = format!("/apps/{}", state["name"].as_str().unwrap());
+        let (status, body) = self.send(Method::PUT, &path, state).await;
+
+        assert_eq!(status, StatusCode::OK, "{body}");
+    }
+}
+
+impl Drop for TestBran {
+    fn drop(&mut self) {
+        self.shutdown.trigger();
+        let _ = std::fs::remove_file(&self.audit_path);
+    }
+}
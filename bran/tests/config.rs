@@ -0,0 +1,74 @@
+use std::sync::Mutex;
+
+use url::Url;
+use uuid::Uuid;
+
+use bran::config::{load, Config, ConfigArgs};
+
+/// `load` reads the environment, which the tests share
+static ENV: Mutex<()> = Mutex::new(());
+
+#[test]
+fn there_is_no_default_dothing_unless_configured() {
+    let _env = ENV.lock().unwrap();
+
+    assert!(Config::default().planner.default_target().is_none());
+
+    let args = ConfigArgs {
+        dothing: Some(Url::parse("http://dothing:8050").unwrap()),
+        ..ConfigArgs::default()
+    };
+    let target = load(&args).unwrap().planner.default_target().unwrap();
+
+    assert_eq!(target.url.as_str(), "http://dothing:8050/");
+    assert!(target.auth_header.is_none());
+}
+
+#[test]
+fn environment_values_take_the_type_of_their_setting() {
+    let _env = ENV.lock().unwrap();
+
+    std::env::set_var("dothing_auth", "12345");
+    std::env::set_var("history_size", "7");
+    std::env::set_var("require_approval", "true");
+    let config = load(&ConfigArgs::default());
+
+    std::env::set_var("history_size", "many");
+    let invalid = load(&ConfigArgs::default());
+
+    std::env::set_var("history_size", "7");
+    std::env::set_var("require_approval", "yes");
+    let invalid_flag = load(&ConfigArgs::default());
+
+    std::env::remove_var("dothing_auth");
+    std::env::remove_var("history_size");
+    std::env::remove_var("require_approval");
+
+    let config = config.unwrap();
+    assert_eq!(config.planner.dothing_auth.as_deref(), Some("12345"));
+    assert_eq!(config.register.history_size, 7);
+    assert!(config.planner.require_approval);
+    assert!(invalid.unwrap_err().to_string().contains("history_size"));
+    assert!(invalid_flag
+        .unwrap_err()
+        .to_string()
+        .contains("require_approval must be true or false"));
+}
+
+#[test]
+fn settings_under_a_value_are_an_error() {
+    let _env = ENV.lock().unwrap();
+
+    let path = std::env::temp_dir().join(format!("bran-config-{}.toml", Uuid::new_v4()));
+    std::fs::write(&path, "server = 5\n").unwrap();
+
+    let args = ConfigArgs {
+        config: Some(path.clone()),
+        port: Some(8080),
+        ..ConfigArgs::default()
+    };
+    let error = load(&args).unwrap_err();
+
+    std::fs::remove_file(path).unwrap();
+    assert!(error.to_string().contains("server.port"), "{error}");
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use common::{addition, reconfig, restart, spec, MockDothing, TestBran};
+
+/// Every kind of location directive, with an order it accepts.
+fn orders() -> [(&'static str, Value); 3] {
+    [
+        ("addition", addition()),
+        ("reconfig", reconfig()),
+        ("restart", restart()),
+    ]
+}
+
+#[tokio::test]
+async fn directives_for_unknown_apps_are_not_found() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    for (kind, order) in orders() {
+        let path = format!("/directives/{kind}/ghost/l1");
+        let (status, body) = bran.send(Method::POST, &path, &order).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND, "{kind}");
+        assert_eq!(body["msg"], "Couldn't find application in register");
+    }
+
+    assert!(bran.register.lock().unwrap().directives.is_empty());
+}
+
+#[tokio::test]
+async fn directives_for_unknown_locations_are_not_found() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    bran.register(&spec("farm", 1)).await;
+
+    for (kind, order) in orders() {
+        let path = format!("/directives/{kind}/farm/barn");
+        let (status, body) = bran.send(Method::POST, &path, &order).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND, "{kind}");
+        assert!(
+            body["msg"]
+                .as_str()
+                .unwrap()
+                .starts_with("Couldn't find location barn"),
+            "{body}"
+        );
+    }
+
+    assert!(!bran
+        .register
+        .lock()
+        .unwrap()
+        .directives
+        .contains_key("farm"));
+}
+
+#[tokio::test]
+async fn directives_of_a_location_are_kept_together() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    bran.register(&spec("farm", 1)).await;
+
+    for (kind, order) in orders() {
+        bran.direct(kind, "farm", "l1", &order).await;
+    }
+
+    let (status, directives) = bran.get("/directives/farm").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(directives["l1"]["addition"], addition());
+    assert_eq!(directives["l1"]["reconfig"], reconfig());
+    assert_eq!(directives["l1"]["restart"], restart());
+}
+
+#[tokio::test]
+async fn targets_and_webhooks_of_unknown_apps_are_not_found() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    let target = json!({"url": dothing.url});
+    let webhook = json!({"url": "http://localhost:9/hook"});
+
+    for (path, body) in [
+        ("/directives/target/ghost", target),
+        ("/directives/webhook/ghost", webhook),
+    ] {
+        let (status, body) = bran.send(Method::POST, path, &body).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND, "{path}");
+        assert_eq!(body["msg"], "Couldn't find application in register");
+    }
+}
+
+#[tokio::test]
+async fn state_of_unknown_apps_is_not_found() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    let ghost = spec("ghost", 1);
+
+    for method in [Method::PUT, Method::PATCH] {
+        let (status, _) = bran.send(method.clone(), "/apps/ghost", &ghost).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND, "{method}");
+    }
+}
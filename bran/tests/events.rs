@@ -0,0 +1,183 @@
+mod common;
+
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use bran::manifests::ManifestSync;
+
+use common::{addition, spec, MockDothing, TestBran};
+
+/// How long a stream is given to deliver an event.
+const EVENT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// A subscription to `/events`, read one server-sent event at a time.
+struct EventStream {
+    response: reqwest::Response,
+    buffer: String,
+}
+
+impl EventStream {
+    async fn open(bran: &TestBran, query: &str) -> Self {
+        let response = reqwest::get(format!("{}/events{query}", bran.url))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        Self {
+            response,
+            buffer: String::new(),
+        }
+    }
+
+    /// The type and data of the next event, or `None` if none came in time.
+    async fn next(&mut self) -> Option<(String, Value)> {
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let block = self.buffer[..end].to_owned();
+                self.buffer.drain(..end + 2);
+
+                let field = |name: &str| {
+                    block
+                        .lines()
+                        .find_map(|l| l.strip_prefix(name))
+                        .map(|v| v.trim().to_owned())
+                };
+
+                // Keep-alive comments have neither
+                if let (Some(kind), Some(data)) = (field("event:"), field("data:")) {
+                    return Some((kind, serde_json::from_str(&data).unwrap()));
+                }
+                continue;
+            }
+
+            let chunk = tokio::time::timeout(EVENT_TIMEOUT, self.response.chunk())
+                .await
+                .ok()?
+                .unwrap()?;
+            self.buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    }
+
+    /// Every event until the stream goes quiet, as type and app.
+    async fn drain(&mut self) -> Vec<(String, Option<String>)> {
+        let mut events = Vec::new();
+
+        while let Some((kind, data)) = self.next().await {
+            events.push((kind, data["app"].as_str().map(str::to_owned)));
+        }
+
+        events
+    }
+}
+
+fn event(kind: &str, app: &str) -> (String, Option<String>) {
+    (kind.to_owned(), Some(app.to_owned()))
+}
+
+#[tokio::test]
+async fn streams_only_carry_the_asked_for_app() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+
+    let mut farm = EventStream::open(&bran, "?app=farm").await;
+    let mut all = EventStream::open(&bran, "").await;
+
+    bran.register(&spec("farm", 1)).await;
+    bran.register(&spec("barn", 1)).await;
+    bran.direct("addition", "farm", "l1", &addition()).await;
+    bran.step().await;
+
+    let farm_events = farm.drain().await;
+    assert_eq!(
+        farm_events[..3],
+        [
+            event("app_registered", "farm"),
+            event("directives_changed", "farm"),
+            ("planner_cycle_started".to_owned(), None),
+        ]
+    );
+    assert!(farm_events
+        .iter()
+        .all(|(_, app)| app.as_deref() != Some("barn")));
+
+    let all_events = all.drain().await;
+    assert!(all_events.contains(&event("app_registered", "barn")));
+    assert!(all_events.contains(&event("app_registered", "farm")));
+}
+
+#[tokio::test]
+async fn imports_publish_what_they_change() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    bran.register(&spec("farm", 1)).await;
+    let mut stream = EventStream::open(&bran, "").await;
+
+    let snapshot = json!({
+        "version": 1,
+        "exported_at": "2024-01-01T00:00:00Z",
+        "apps": {"barn": {"application": spec("barn", 1)}},
+    });
+    let (status, body) = bran
+        .send(Method::POST, "/register/import?mode=replace", &snapshot)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    assert_eq!(
+        stream.drain().await,
+        [
+            event("app_registered", "barn"),
+            event("app_removed", "farm")
+        ]
+    );
+
+    // Dry runs change nothing
+    let (status, _) = bran
+        .send(
+            Method::POST,
+            "/register/import?mode=replace&dry_run=true",
+            &json!({"version": 1, "exported_at": "2024-01-01T00:00:00Z", "apps": {}}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(stream.drain().await.is_empty());
+}
+
+#[tokio::test]
+async fn manifest_syncs_publish_what_they_change() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    let dir = std::env::temp_dir().join(format!("bran-events-{}", Uuid::new_v4()));
+    fs::create_dir(&dir).unwrap();
+    let sync = ManifestSync::new(Arc::clone(&bran.register), dir.clone())
+        .with_events(Arc::clone(&bran.events));
+
+    let mut stream = EventStream::open(&bran, "").await;
+
+    let manifest = json!({
+        "app": "farm",
+        "application": spec("farm", 1),
+        "directives": {"l1": {"addition": addition()}},
+    });
+    fs::write(dir.join("farm.json"), manifest.to_string()).unwrap();
+    sync.sync();
+
+    fs::remove_file(dir.join("farm.json")).unwrap();
+    sync.sync();
+    fs::remove_dir(&dir).unwrap();
+
+    assert_eq!(
+        stream.drain().await,
+        [
+            event("app_registered", "farm"),
+            event("directives_changed", "farm"),
+            event("app_removed", "farm"),
+        ]
+    );
+}
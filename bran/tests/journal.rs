@@ -0,0 +1,118 @@
+mod common;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::Utc;
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+
+use bran::journal::{read_journal, Journal, JournalEntry};
+
+use common::{report, spec, MockDothing, TestBran};
+
+fn temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("bran-journal-{}", Uuid::new_v4()))
+}
+
+fn entry(n: u64) -> JournalEntry {
+    JournalEntry {
+        at: Utc::now(),
+        method: "PUT".to_owned(),
+        path: format!("/apps/app-{n}"),
+        app: Some(format!("app-{n}")),
+        status: 200,
+        redacted: false,
+        body: json!({ "n": n }),
+    }
+}
+
+#[test]
+fn rotated_files_are_read_back_oldest_first() {
+    let dir = temp_dir();
+    let line = serde_json::to_string(&entry(0)).unwrap().len() as u64 + 1;
+
+    // Two entries fit in a file, the oldest of ten are dropped
+    let journal = Journal::open(dir.clone(), 2 * line, 3).unwrap();
+    for n in 0..10 {
+        journal.append(&entry(n)).unwrap();
+    }
+
+    assert!(dir.join("journal.2.jsonl").exists());
+    assert!(!dir.join("journal.3.jsonl").exists());
+
+    let kept = read_journal(&dir)
+        .unwrap()
+        .iter()
+        .map(|entry| entry.body["n"].as_u64().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(kept, [4, 5, 6, 7, 8, 9]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn received_bodies_are_recorded_with_their_status() {
+    let dir = temp_dir();
+    let dothing = MockDothing::start().await;
+    let journal = Arc::new(Journal::open(dir.clone(), 1024 * 1024, 2).unwrap());
+    let bran = TestBran::start_with_journal(&dothing, Some(journal)).await;
+
+    bran.register(&spec("farm", 1)).await;
+    bran.report(&report("farm", 1, &[(Uuid::new_v4(), "Coherent")]))
+        .await;
+    let (status, _) = bran
+        .send(Method::PUT, "/apps/ghost", &report("ghost", 1, &[]))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    bran.get("/apps/farm").await;
+
+    let entries = read_journal(&dir).unwrap();
+    let recorded = entries
+        .iter()
+        .map(|e| (e.method.as_str(), e.path.as_str(), e.status))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        recorded,
+        [
+            ("POST", "/apps/farm", 200),
+            ("PUT", "/apps/farm", 200),
+            ("PUT", "/apps/ghost", 404),
+        ]
+    );
+
+    let update = entries[1].state_update().unwrap();
+    assert_eq!(update.app, "farm");
+    assert!(entries[0].state_update().is_none());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn target_secrets_are_not_recorded() {
+    let dir = temp_dir();
+    let dothing = MockDothing::start().await;
+    let journal = Arc::new(Journal::open(dir.clone(), 1024 * 1024, 2).unwrap());
+    let bran = TestBran::start_with_journal(&dothing, Some(journal)).await;
+
+    bran.register(&spec("farm", 1)).await;
+    let target = json!({
+        "url": dothing.url,
+        "auth_header": "Bearer hunter2",
+    });
+    let (status, body) = bran
+        .send(Method::POST, "/directives/target/farm", &target)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let entries = read_journal(&dir).unwrap();
+    let recorded = entries.last().unwrap();
+
+    assert!(recorded.redacted);
+    assert_eq!(recorded.path, "/directives/target/farm");
+    assert!(!recorded.body.to_string().contains("hunter2"));
+    assert_eq!(recorded.body["url"], target["url"]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+use url::Url;
+use uuid::Uuid;
+
+use bran::aggregator::{ApplicationRegister, DothingTarget};
+use bran::config::RegisterConfig;
+use bran::manifests::ManifestSync;
+
+use common::{addition, spec, MockDothing, TestBran};
+
+/// A manifests directory of its own, removed when dropped.
+struct ManifestsDir(PathBuf);
+
+impl ManifestsDir {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("bran-manifests-{}", Uuid::new_v4()));
+        fs::create_dir(&dir).unwrap();
+
+        Self(dir)
+    }
+
+    fn write(&self, file: &str, content: &str) {
+        fs::write(self.0.join(file), content).unwrap();
+    }
+
+    fn remove(&self, file: &str) {
+        fs::remove_file(self.0.join(file)).unwrap();
+    }
+}
+
+impl Drop for ManifestsDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn invalid_manifests_keep_what_they_declared_last() {
+    let dir = ManifestsDir::new();
+    let register = Arc::new(Mutex::new(ApplicationRegister::new(
+        &RegisterConfig::default(),
+    )));
+    let sync = ManifestSync::new(Arc::clone(&register), dir.0.clone());
+
+    let manifest = json!({"app": "farm", "application": spec("farm", 1)});
+    dir.write("farm.json", &manifest.to_string());
+    sync.sync();
+
+    let target = DothingTarget::new(Url::parse("http://dothing:8050").unwrap());
+    register
+        .lock()
+        .unwrap()
+        .targets
+        .insert("farm".to_owned(), target);
+
+    dir.write("farm.json", "{\"app\": \"farm\",");
+    sync.sync();
+
+    {
+        let register = register.lock().unwrap();
+        assert!(register.specs.contains_key("farm"));
+        assert!(register.managed_spec("farm").is_some());
+    }
+
+    dir.remove("farm.json");
+    sync.sync();
+
+    let register = register.lock().unwrap();
+    assert!(!register.specs.contains_key("farm"));
+    assert!(register.managed_spec("farm").is_none());
+    assert!(register.targets.contains_key("farm"));
+}
+
+#[test]
+fn manifests_declaring_a_loaded_app_again_keep_theirs() {
+    let dir = ManifestsDir::new();
+    let register = Arc::new(Mutex::new(ApplicationRegister::new(
+        &RegisterConfig::default(),
+    )));
+    let sync = ManifestSync::new(Arc::clone(&register), dir.0.clone());
+
+    dir.write(
+        "a.json",
+        &json!({"app": "barn", "application": spec("barn", 1)}).to_string(),
+    );
+    dir.write(
+        "b.json",
+        &json!({"app": "farm", "application": spec("farm", 1)}).to_string(),
+    );
+    sync.sync();
+
+    // b.json now clashes with a.json, so it is skipped as a whole
+    dir.write(
+        "b.json",
+        &json!({"app": "barn", "application": spec("barn", 2)}).to_string(),
+    );
+    sync.sync();
+
+    let register = register.lock().unwrap();
+    assert!(register.specs.contains_key("farm"));
+    assert_eq!(
+        register.managed_spec("barn"),
+        Some(dir.0.join("a.json").as_path())
+    );
+}
+
+#[tokio::test]
+async fn exports_with_manifest_apps_import_back() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+    let dir = ManifestsDir::new();
+
+    // barn's objective comes from the API, only its directives are managed
+    bran.register(&spec("barn", 1)).await;
+
+    let directives = json!({"l1": {"addition": addition()}});
+    dir.write(
+        "farm.json",
+        &json!({"app": "farm", "application": spec("farm", 1), "directives": directives})
+            .to_string(),
+    );
+    dir.write(
+        "barn.json",
+        &json!({"app": "barn", "directives": directives}).to_string(),
+    );
+    ManifestSync::new(Arc::clone(&bran.register), dir.0.clone()).sync();
+
+    let (status, snapshot) = bran.get("/register/export").await;
+    assert_eq!(status, StatusCode::OK, "{snapshot}");
+
+    for mode in ["merge", "replace"] {
+        let path = format!("/register/import?mode={mode}");
+        let (status, body) = bran.send(Method::POST, &path, &snapshot).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["changes"], json!([]), "{mode}");
+    }
+
+    // Only what the manifests don't declare is imported
+    let mut changed = snapshot.clone();
+    changed["apps"]["farm"]["application"] = spec("farm", 3);
+    changed["apps"]["farm"]["directives"] = json!({});
+    changed["apps"]["barn"]["application"] = spec("barn", 3);
+    changed["apps"]["barn"]["directives"] = json!({});
+
+    let (status, body) = bran.send(Method::POST, "/register/import", &changed).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let register = bran.register.lock().unwrap();
+    assert_eq!(
+        serde_json::to_value(&register.specs["farm"]).unwrap(),
+        snapshot["apps"]["farm"]["application"]
+    );
+    assert_eq!(
+        serde_json::to_value(&register.specs["barn"]).unwrap(),
+        changed["apps"]["barn"]["application"]
+    );
+    assert!(register.directives["farm"].contains_key("l1"));
+    assert!(register.directives["barn"].contains_key("l1"));
+}
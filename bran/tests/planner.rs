@@ -0,0 +1,393 @@
+mod common;
+
+use axum::http::StatusCode;
+use reqwest::Method;
+use serde_json::json;
+use uuid::Uuid;
+
+use bran::planner::{PlannerState, ProblemInfo};
+
+use common::{addition, reconfig, report, restart, spec, MockDothing, TestBran};
+
+const ADDITION: &str = "/addition";
+const RESTART: &str = "/restart";
+const RECONFIG: &str = "/reconfig/http";
+
+/// An app with every directive set on `l1`.
+async fn directed_app(bran: &TestBran, app: &str, count: usize) {
+    bran.register(&spec(app, count)).await;
+    bran.direct("addition", app, "l1", &addition()).await;
+    bran.direct("restart", app, "l1", &restart()).await;
+    bran.direct("reconfig", app, "l1", &reconfig()).await;
+}
+
+#[tokio::test]
+async fn faulty_sensor_escalates_from_restart_to_replacement() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+    let sensor = Uuid::new_v4();
+
+    directed_app(&bran, "farm", 1).await;
+    bran.report(&report("farm", 1, &[(sensor, "Fault")])).await;
+
+    assert_eq!(bran.step().await, 1);
+    let calls = dothing.calls();
+    assert_eq!(calls[0].endpoint, RESTART);
+    assert_eq!(calls[0].body["uuid"], sensor.to_string());
+    dothing.take_endpoints();
+
+    assert_eq!(bran.step().await, 1);
+    let calls = dothing.calls();
+    assert_eq!(calls[0].endpoint, RECONFIG);
+    assert_eq!(calls[0].body["uuid"], sensor.to_string());
+    dothing.take_endpoints();
+
+    // Neither helped, so the sensor gets a replacement
+    assert_eq!(bran.step().await, 1);
+    let calls = dothing.calls();
+    assert_eq!(calls[0].endpoint, ADDITION);
+    assert!(calls[0].body["env_vars"]["device_uuid"].is_string());
+    assert!(calls[0].body["args"]
+        .as_array()
+        .unwrap()
+        .contains(&"location:l1".into()));
+    dothing.take_endpoints();
+
+    // The replacement hasn't shown up yet
+    assert_eq!(bran.step().await, 0);
+    assert!(dothing.calls().is_empty());
+}
+
+#[tokio::test]
+async fn missing_sensors_are_added_once() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+
+    directed_app(&bran, "farm", 2).await;
+    bran.report(&report("farm", 2, &[])).await;
+
+    assert_eq!(bran.step().await, 2);
+    assert_eq!(dothing.take_endpoints(), [ADDITION, ADDITION]);
+
+    // Both additions are pending, nothing more is ordered
+    assert_eq!(bran.step().await, 0);
+    assert!(dothing.calls().is_empty());
+
+    let allocations = bran.get("/planner/allocations/farm").await.1;
+    assert_eq!(allocations.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn sensor_gone_after_a_restart_is_replaced() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+    let sensor = Uuid::new_v4();
+
+    directed_app(&bran, "farm", 1).await;
+    bran.report(&report("farm", 1, &[(sensor, "Fault")])).await;
+
+    assert_eq!(bran.step().await, 1);
+    assert_eq!(dothing.take_endpoints(), [RESTART]);
+
+    // A missing sensor is a shortfall, not a fault to escalate
+    bran.report(&report("farm", 1, &[])).await;
+    assert_eq!(bran.step().await, 1);
+    assert_eq!(dothing.take_endpoints(), [ADDITION]);
+}
+
+#[tokio::test]
+async fn coherent_app_gets_no_orders() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+
+    directed_app(&bran, "farm", 1).await;
+    bran.report(&report("farm", 1, &[(Uuid::new_v4(), "Coherent")]))
+        .await;
+
+    assert_eq!(bran.step().await, 0);
+    assert!(dothing.calls().is_empty());
+}
+
+#[tokio::test]
+async fn apps_that_never_reported_get_no_orders() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+
+    directed_app(&bran, "farm", 2).await;
+
+    assert_eq!(bran.step().await, 0);
+    assert!(dothing.calls().is_empty());
+}
+
+#[tokio::test]
+async fn problems_without_a_directive_are_skipped() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+
+    bran.register(&spec("farm", 1)).await;
+    bran.direct("addition", "farm", "l1", &addition()).await;
+    bran.report(&report("farm", 1, &[(Uuid::new_v4(), "Fault")]))
+        .await;
+
+    assert_eq!(bran.step().await, 0);
+    assert!(dothing.calls().is_empty());
+}
+
+#[tokio::test]
+async fn circuit_opens_when_dothing_keeps_failing() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+
+    dothing.answer(ADDITION, StatusCode::INTERNAL_SERVER_ERROR);
+    directed_app(&bran, "farm", 5).await;
+    bran.report(&report("farm", 5, &[])).await;
+
+    // As many failures as the default threshold
+    assert_eq!(bran.step().await, 5);
+    assert_eq!(dothing.take_endpoints().len(), 5);
+    assert!(!bran.planner_state.lock().unwrap().circuits_closed());
+
+    // Held back until the next probe
+    assert_eq!(bran.step().await, 0);
+    assert!(dothing.calls().is_empty());
+
+    let (_, ready) = bran.get("/readyz").await;
+    assert_eq!(ready["circuit"], false, "{ready}");
+}
+
+#[tokio::test]
+async fn refused_probes_close_the_circuit() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+
+    bran.configure(|config| {
+        config.circuit.failure_threshold = 1;
+        config.circuit.probe_interval_secs = 0;
+    });
+    dothing.answer(ADDITION, StatusCode::INTERNAL_SERVER_ERROR);
+    directed_app(&bran, "farm", 3).await;
+    bran.report(&report("farm", 3, &[])).await;
+
+    bran.step().await;
+    assert!(!bran.planner_state.lock().unwrap().circuits_closed());
+    dothing.take_endpoints();
+
+    // dothing answers the probe, even if it refuses the order
+    dothing.answer(ADDITION, StatusCode::BAD_REQUEST);
+    bran.step().await;
+    assert_eq!(dothing.take_endpoints(), [ADDITION]);
+    assert!(bran.planner_state.lock().unwrap().circuits_closed());
+
+    dothing.answer(ADDITION, StatusCode::OK);
+    bran.step().await;
+    assert_eq!(dothing.take_endpoints(), [ADDITION; 3]);
+}
+
+#[tokio::test]
+async fn refused_additions_are_not_waited_on() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+
+    dothing.answer(ADDITION, StatusCode::BAD_REQUEST);
+    directed_app(&bran, "farm", 1).await;
+    bran.report(&report("farm", 1, &[])).await;
+
+    assert_eq!(bran.step().await, 1);
+    assert_eq!(dothing.take_endpoints(), [ADDITION]);
+
+    let (_, allocations) = bran.get("/planner/allocations/farm").await;
+    assert!(
+        allocations.as_array().is_none_or(|a| a.is_empty()),
+        "{allocations}"
+    );
+
+    // Nothing is pending, so the addition goes out again
+    assert_eq!(bran.step().await, 1);
+    assert_eq!(dothing.take_endpoints(), [ADDITION]);
+}
+
+#[tokio::test]
+async fn refused_orders_are_counted_as_failures() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+
+    dothing.answer(RESTART, StatusCode::SERVICE_UNAVAILABLE);
+    directed_app(&bran, "farm", 1).await;
+    bran.report(&report("farm", 1, &[(Uuid::new_v4(), "Fault")]))
+        .await;
+
+    assert_eq!(bran.step().await, 1);
+
+    let metrics = reqwest::get(format!("{}/metrics", bran.url))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert!(
+        metrics.contains(r#"bran_orders_total{kind="Restart",outcome="failure"} 1"#),
+        "{metrics}"
+    );
+    assert!(!metrics.contains(r#"outcome="success""#), "{metrics}");
+}
+
+#[tokio::test]
+async fn orders_that_cant_be_built_spend_no_budget() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+
+    bran.configure(|config| config.limits.max_actions_per_app = Some(1));
+
+    // Two data keys in the args, which the addition can't be built from
+    let mut broken = addition();
+    broken["args"] = json!(["key:a", "key:b"]);
+    bran.register(&spec("farm", 1)).await;
+    bran.direct("addition", "farm", "l1", &broken).await;
+    bran.report(&report("farm", 1, &[])).await;
+
+    assert_eq!(bran.step().await, 0);
+    assert!(dothing.calls().is_empty());
+
+    let deferred = bran.get("/planner/deferred").await.1;
+    assert_eq!(deferred.as_array().unwrap().len(), 1);
+    assert_eq!(deferred[0]["kind"], "Addition");
+    assert_eq!(
+        deferred[0]["reason"],
+        "could not be built: More than one key= string found"
+    );
+
+    // Once fixed, the addition still has the budget of the app
+    bran.direct("addition", "farm", "l1", &addition()).await;
+
+    assert_eq!(bran.step().await, 1);
+    assert_eq!(dothing.take_endpoints(), [ADDITION]);
+    assert!(bran.planner_state.lock().unwrap().deferred.is_empty());
+}
+
+#[tokio::test]
+async fn deferred_orders_go_first_on_the_next_cycle() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+    let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+
+    bran.configure(|config| config.limits.max_actions_per_cycle = Some(1));
+
+    directed_app(&bran, "barn", 1).await;
+    directed_app(&bran, "farm", 1).await;
+    bran.report(&report("barn", 1, &[(first, "Fault")])).await;
+    bran.report(&report("farm", 1, &[(second, "Fault")])).await;
+
+    assert_eq!(bran.step().await, 1);
+    assert_eq!(dothing.calls()[0].body["uuid"], first.to_string());
+    dothing.take_endpoints();
+    assert_eq!(bran.planner_state.lock().unwrap().deferred.len(), 1);
+
+    // barn would escalate now, but farm has been waiting
+    assert_eq!(bran.step().await, 1);
+    let calls = dothing.calls();
+    assert_eq!(calls[0].endpoint, RESTART);
+    assert_eq!(calls[0].body["uuid"], second.to_string());
+}
+
+#[tokio::test]
+async fn orders_held_back_by_an_opening_circuit_keep_their_place() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+
+    bran.configure(|config| {
+        config.limits.max_in_flight = 1;
+        config.circuit.failure_threshold = 1;
+    });
+    dothing.answer(ADDITION, StatusCode::INTERNAL_SERVER_ERROR);
+    directed_app(&bran, "farm", 3).await;
+    bran.report(&report("farm", 3, &[])).await;
+
+    // The first addition opens the circuit, the other two wait for it
+    bran.step().await;
+    assert_eq!(dothing.take_endpoints(), [ADDITION]);
+
+    let deferred = bran.planner_state.lock().unwrap().deferred.clone();
+    assert_eq!(deferred.len(), 2);
+    assert!(deferred.iter().all(|d| d.problem.device_uuid.is_none()));
+    let since = deferred[0].deferred_since;
+
+    bran.step().await;
+
+    let deferred = bran.planner_state.lock().unwrap().deferred.clone();
+    assert_eq!(deferred.len(), 3);
+    assert_eq!(
+        deferred
+            .iter()
+            .filter(|d| d.deferred_since == since)
+            .count(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn pending_additions_survive_a_restart() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+    let path = std::env::temp_dir().join(format!("bran-planner-{}.json", Uuid::new_v4()));
+
+    directed_app(&bran, "farm", 2).await;
+    bran.report(&report("farm", 2, &[])).await;
+    assert_eq!(bran.step().await, 2);
+
+    bran.planner_state.lock().unwrap().save(&path).unwrap();
+    let mut restored = PlannerState::restore(Some(&path)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let problem = ProblemInfo::new("l1", "temp", &None);
+    assert_eq!(restored.allocations.pending_count("farm", &problem), 2);
+
+    // Device uuids carry on from where they were
+    let sent = restored
+        .allocations
+        .get("farm")
+        .unwrap()
+        .iter()
+        .map(|a| a.device_uuid)
+        .collect::<Vec<_>>();
+    let next = restored.allocations.allocate("farm", &problem);
+    assert!(!sent.contains(&next));
+}
+
+#[tokio::test]
+async fn orders_wait_for_approval_when_required() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+    let sensor = Uuid::new_v4();
+    bran.configure(|c| c.require_approval = true);
+
+    directed_app(&bran, "farm", 1).await;
+    bran.report(&report("farm", 1, &[(sensor, "Fault")])).await;
+
+    assert_eq!(bran.step().await, 0);
+    assert!(dothing.calls().is_empty());
+
+    let deferred = bran.get("/planner/deferred").await.1;
+    assert_eq!(deferred[0]["kind"], "Restart");
+    assert_eq!(deferred[0]["reason"], "awaiting approval");
+
+    let approve = |app: &str| format!("/planner/deferred/{app}/approve");
+    let (status, body) = bran
+        .send(Method::POST, &approve("farm"), &json!(null))
+        .await;
+    assert!(status.is_success(), "{body}");
+    assert_eq!(body["approved"].as_array().unwrap().len(), 1);
+
+    assert_eq!(bran.step().await, 1);
+    assert_eq!(dothing.take_endpoints(), [RESTART]);
+
+    // The approval was spent, the reconfiguration that follows needs its own
+    assert_eq!(bran.step().await, 0);
+    let deferred = bran.get("/planner/deferred").await.1;
+    assert_eq!(deferred[0]["kind"], "Reconfigure");
+
+    let (status, _) = bran
+        .send(Method::POST, &approve("ghost"), &json!(null))
+        .await;
+    assert_eq!(status.as_u16(), 404);
+}
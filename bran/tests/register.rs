@@ -0,0 +1,172 @@
+mod common;
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use common::{addition, spec, MockDothing, TestBran};
+
+/// Registers `farm` with a dothing target and a webhook, both with secrets.
+async fn farm_with_secrets(bran: &TestBran, dothing: &MockDothing) {
+    bran.register(&spec("farm", 1)).await;
+
+    let target = json!({"url": dothing.url, "auth_header": "Bearer farm-token"});
+    let (status, body) = bran
+        .send(Method::POST, "/directives/target/farm", &target)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let webhook = json!({"url": "http://127.0.0.1:9/hook", "secret": "farm-secret"});
+    let (status, body) = bran
+        .send(Method::POST, "/directives/webhook/farm", &webhook)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+}
+
+#[tokio::test]
+async fn exports_hide_secrets_and_imports_keep_them() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    farm_with_secrets(&bran, &dothing).await;
+
+    let (status, snapshot) = bran.get("/register/export").await;
+    assert_eq!(status, StatusCode::OK, "{snapshot}");
+    assert_eq!(
+        snapshot["apps"]["farm"]["target"]["auth_header"],
+        "<redacted>"
+    );
+    assert_eq!(snapshot["apps"]["farm"]["webhook"]["secret"], "<redacted>");
+
+    // Authentication is disabled, so nobody proves to be an admin
+    let (status, _) = bran.get("/register/export?secrets=true").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = bran.send(Method::POST, "/register/import", &snapshot).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["changes"], json!([]));
+
+    let register = bran.register.lock().unwrap();
+    assert_eq!(
+        register.targets["farm"].auth_header.as_deref(),
+        Some("Bearer farm-token")
+    );
+    assert_eq!(
+        register.webhooks["farm"].secret.as_deref(),
+        Some("farm-secret")
+    );
+}
+
+#[tokio::test]
+async fn redacted_secrets_with_nothing_to_keep_are_refused() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    farm_with_secrets(&bran, &dothing).await;
+    let (_, mut snapshot) = bran.get("/register/export").await;
+
+    snapshot["apps"]["barn"] = snapshot["apps"]["farm"].clone();
+    snapshot["apps"]["barn"]["application"]["name"] = json!("barn");
+
+    let (status, body) = bran.send(Method::POST, "/register/import", &snapshot).await;
+    assert_eq!(status, StatusCode::CONFLICT, "{body}");
+    assert!(!bran.register.lock().unwrap().specs.contains_key("barn"));
+}
+
+#[tokio::test]
+async fn dry_runs_report_changed_secrets_without_their_values() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    farm_with_secrets(&bran, &dothing).await;
+    let (_, mut snapshot) = bran.get("/register/export").await;
+
+    snapshot["apps"]["farm"]["target"]["auth_header"] = json!("Bearer new-token");
+
+    let (status, body) = bran
+        .send(Method::POST, "/register/import?dry_run=true", &snapshot)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(
+        body["changes"],
+        json!([{
+            "path": "/farm/target/auth_header",
+            "before": "<redacted>",
+            "after": "<redacted>",
+        }])
+    );
+    assert!(!body.to_string().contains("token"), "{body}");
+}
+
+#[tokio::test]
+async fn audit_entries_report_changed_secrets_without_their_values() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    farm_with_secrets(&bran, &dothing).await;
+
+    let webhook = json!({"url": "http://127.0.0.1:9/hook", "secret": "other-secret"});
+    bran.send(Method::POST, "/directives/webhook/farm", &webhook)
+        .await;
+
+    let (_, mut snapshot) = bran.get("/register/export").await;
+    snapshot["apps"]["farm"]["target"]["auth_header"] = json!("Bearer new-token");
+    bran.send(Method::POST, "/register/import", &snapshot).await;
+
+    let (status, entries) = bran.get("/audit").await;
+    assert_eq!(status, StatusCode::OK, "{entries}");
+    assert!(!entries.to_string().contains("-secret"), "{entries}");
+
+    let changes = |path: &str| -> Value {
+        entries
+            .as_array()
+            .unwrap()
+            .iter()
+            .rfind(|e| e["path"] == path)
+            .unwrap()["changes"]
+            .clone()
+    };
+
+    assert_eq!(
+        changes("/directives/webhook/farm"),
+        json!([{"path": "/webhook/secret", "before": "<redacted>", "after": "<redacted>"}])
+    );
+    assert_eq!(
+        changes("/register/import"),
+        json!([{"path": "/farm/target/auth_header", "before": "<redacted>", "after": "<redacted>"}])
+    );
+}
+
+#[tokio::test]
+async fn rollbacks_keep_the_current_target_and_webhook() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    farm_with_secrets(&bran, &dothing).await;
+    bran.direct("addition", "farm", "l1", &addition()).await;
+
+    let target = json!({"url": "http://127.0.0.1:9", "auth_header": "Bearer new-token"});
+    let (status, body) = bran
+        .send(Method::POST, "/directives/target/farm", &target)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let (status, body) = bran
+        .send(Method::POST, "/apps/farm/rollback/1", &Value::Null)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let register = bran.register.lock().unwrap();
+    assert!(register
+        .directives
+        .get("farm")
+        .is_none_or(|directives| directives.is_empty()));
+    assert_eq!(register.targets["farm"].url.as_str(), "http://127.0.0.1:9/");
+    assert_eq!(
+        register.targets["farm"].auth_header.as_deref(),
+        Some("Bearer new-token")
+    );
+    assert_eq!(
+        register.webhooks["farm"].secret.as_deref(),
+        Some("farm-secret")
+    );
+}
@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use bran::config::{load, ConfigArgs, PlannerConfig, Reloader};
+use bran::telemetry::{LogLevel, Telemetry};
+
+/// How long a reload may take, file watching included.
+const RELOAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn settings(max_in_flight: usize, level: &str) -> String {
+    format!("[planner.limits]\nmax_in_flight = {max_in_flight}\n\n[logging]\nlevel = \"{level}\"\n")
+}
+
+/// A config file of its own, removed when dropped.
+struct ConfigFile(PathBuf);
+
+impl ConfigFile {
+    fn new(content: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("bran-reload-{}.toml", Uuid::new_v4()));
+        std::fs::write(&path, content).unwrap();
+
+        Self(path)
+    }
+
+    fn write(&self, content: &str) {
+        std::fs::write(&self.0, content).unwrap();
+    }
+}
+
+impl Drop for ConfigFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// A running reloader of `file`, watching it if `watch_file` is set, with
+/// the planner settings it sends and the log level it changes.
+struct Reloading {
+    planner: watch::Receiver<PlannerConfig>,
+    log_level: LogLevel,
+    // Keeps the filter the log level changes alive
+    _subscriber: Box<dyn tracing::Subscriber + Send + Sync>,
+}
+
+impl Reloading {
+    async fn start(file: &ConfigFile, watch_file: bool) -> Self {
+        let args = ConfigArgs {
+            config: Some(file.0.clone()),
+            ..ConfigArgs::default()
+        };
+        let config = load(&args).unwrap();
+
+        let (subscriber, telemetry) = Telemetry::build(&config.logging).unwrap();
+        let (planner_config, planner) = watch::channel(config.planner.clone());
+        let log_level = telemetry.log_level();
+
+        let reloader = Reloader::new(args, config, planner_config, log_level.clone());
+        tokio::spawn(reloader.run(watch_file.then(|| file.0.clone())));
+
+        // Lets it listen for signals and file changes
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        Self {
+            planner,
+            log_level,
+            _subscriber: Box::new(subscriber),
+        }
+    }
+
+    async fn reloaded(&mut self) -> bool {
+        tokio::time::timeout(RELOAD_TIMEOUT, self.planner.changed())
+            .await
+            .is_ok()
+    }
+
+    fn max_in_flight(&self) -> usize {
+        self.planner.borrow().limits.max_in_flight
+    }
+}
+
+fn hang_up() {
+    let status = Command::new("kill")
+        .args(["-HUP", &std::process::id().to_string()])
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+}
+
+#[tokio::test]
+async fn file_changes_apply_planner_limits_and_log_level() {
+    let file = ConfigFile::new(&settings(4, "error"));
+    let mut reloading = Reloading::start(&file, true).await;
+
+    file.write(&settings(9, "debug"));
+
+    assert!(reloading.reloaded().await);
+    assert_eq!(reloading.max_in_flight(), 9);
+    assert_eq!(reloading.log_level.current().as_deref(), Some("debug"));
+
+    // Invalid configs are refused as a whole
+    file.write("[planner.limits\nmax_in_flight = 1\n");
+
+    assert!(!reloading.reloaded().await);
+    assert_eq!(reloading.max_in_flight(), 9);
+    assert_eq!(reloading.log_level.current().as_deref(), Some("debug"));
+}
+
+#[tokio::test]
+async fn hangups_reload_the_config() {
+    let file = ConfigFile::new(&settings(4, "error"));
+    let mut reloading = Reloading::start(&file, false).await;
+
+    file.write(&settings(2, "info"));
+    hang_up();
+
+    assert!(reloading.reloaded().await);
+    assert_eq!(reloading.max_in_flight(), 2);
+    assert_eq!(reloading.log_level.current().as_deref(), Some("info"));
+}
@@ -0,0 +1,173 @@
+mod common;
+
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use common::{addition, report, spec, MockDothing};
+
+/// A bran binary with its config, state files and register of its own,
+/// whose planner sends `farm` an addition on its first cycle.
+struct Bran {
+    dir: PathBuf,
+    config: PathBuf,
+}
+
+impl Bran {
+    fn new(dothing: &MockDothing, shutdown_timeout_secs: u64) -> Self {
+        let dir = std::env::temp_dir().join(format!("bran-shutdown-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let config = dir.join("bran.toml");
+        std::fs::write(
+            &config,
+            format!(
+                r#"
+[server]
+port = {port}
+shutdown_timeout_secs = {shutdown_timeout_secs}
+
+[audit]
+log = "{dir}/audit.log"
+
+[register]
+state_file = "{dir}/state.json"
+
+[planner]
+interval_secs = 3600
+"#,
+                dir = dir.display()
+            ),
+        )
+        .unwrap();
+
+        let snapshot = json!({
+            "version": 1,
+            "exported_at": "2024-01-01T00:00:00Z",
+            "apps": {
+                "farm": {
+                    "application": spec("farm", 1),
+                    "status": report("farm", 1, &[]),
+                    "directives": {"l1": {"addition": addition()}},
+                    "target": {"url": dothing.url},
+                },
+            },
+        });
+        let snapshot_path = dir.join("snapshot.json");
+        std::fs::write(&snapshot_path, snapshot.to_string()).unwrap();
+
+        let bran = Self { dir, config };
+        let imported = bran.command().arg("import").arg(&snapshot_path).status();
+        assert!(imported.unwrap().success());
+
+        bran
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_bran"));
+        command
+            .arg("--config")
+            .arg(&self.config)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        command
+    }
+
+    fn planner_state(&self) -> Value {
+        let content = std::fs::read_to_string(self.dir.join("state.planner.json")).unwrap();
+        serde_json::from_str(&content).unwrap()
+    }
+}
+
+impl Drop for Bran {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Waits until dothing got an order, returning the device uuid it is for.
+async fn first_order(dothing: &MockDothing) -> String {
+    for _ in 0..200 {
+        if let Some(call) = dothing.calls().first() {
+            return call.body["env_vars"]["device_uuid"]
+                .as_str()
+                .unwrap()
+                .to_owned();
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    panic!("No order reached dothing");
+}
+
+/// Sends SIGTERM and waits for `child` to exit, returning how long it took.
+async fn terminate(child: &mut Child) -> Duration {
+    let sent = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(sent.success());
+
+    let started = Instant::now();
+
+    loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            assert!(status.success(), "{status}");
+            return started.elapsed();
+        }
+        assert!(
+            started.elapsed() < Duration::from_secs(20),
+            "bran never exited"
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+#[tokio::test]
+async fn orders_in_flight_finish_before_the_state_is_saved() {
+    let dothing = MockDothing::start().await;
+    dothing.delay(Duration::from_secs(2));
+    let bran = Bran::new(&dothing, 30);
+
+    let mut child = bran.command().spawn().unwrap();
+    let device_uuid = first_order(&dothing).await;
+    let took = terminate(&mut child).await;
+
+    assert!(took >= Duration::from_secs(1), "{took:?}");
+
+    let state = bran.planner_state();
+    let allocations = &state["allocations"]["entries"]["farm"];
+    assert_eq!(allocations[0]["device_uuid"], device_uuid);
+    assert_eq!(allocations[0]["status"], "Pending");
+}
+
+#[tokio::test]
+async fn the_state_is_saved_when_the_deadline_passes() {
+    let dothing = MockDothing::start().await;
+    dothing.delay(Duration::from_secs(60));
+    let bran = Bran::new(&dothing, 1);
+
+    let mut child = bran.command().spawn().unwrap();
+    first_order(&dothing).await;
+    let took = terminate(&mut child).await;
+
+    assert!(took < Duration::from_secs(10), "{took:?}");
+
+    // The order never got an answer, so nothing is allocated
+    let state = bran.planner_state();
+    assert!(state["allocations"]["entries"]
+        .get("farm")
+        .is_none_or(|a| a.as_array().unwrap().is_empty()));
+    assert!(bran.dir.join("state.json").is_file());
+}
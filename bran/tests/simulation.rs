@@ -0,0 +1,123 @@
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use url::Url;
+use uuid::Uuid;
+
+use bran::aggregator::RegisterSnapshot;
+use bran::config::PlannerConfig;
+use bran::planner::OrderKind;
+use bran::simulation::{SimulatedOrder, Simulator, StateUpdate};
+
+use common::{addition, reconfig, report, restart, spec};
+
+fn snapshot() -> RegisterSnapshot {
+    let snapshot = json!({
+        "version": 1,
+        "exported_at": "2026-01-01T00:00:00Z",
+        "apps": {
+            "farm": {
+                "application": spec("farm", 1),
+                "directives": {
+                    "l1": {
+                        "addition": addition(),
+                        "restart": restart(),
+                        "reconfig": reconfig(),
+                    },
+                },
+            },
+        },
+    });
+
+    RegisterSnapshot::parse(&snapshot.to_string()).unwrap()
+}
+
+fn config(interval_secs: u64) -> PlannerConfig {
+    PlannerConfig {
+        dothing: Some(Url::parse("http://dothing.invalid").unwrap()),
+        interval_secs,
+        ..PlannerConfig::default()
+    }
+}
+
+fn start() -> DateTime<Utc> {
+    "2026-01-01T10:00:00Z".parse().unwrap()
+}
+
+/// A report of `farm` arriving `secs` after the start.
+fn update(secs: i64, sensors: &[(Uuid, &str)]) -> StateUpdate {
+    StateUpdate {
+        at: start() + Duration::seconds(secs),
+        app: "farm".to_owned(),
+        state: serde_json::from_value(report("farm", 1, sensors)).unwrap(),
+    }
+}
+
+async fn simulate(interval_secs: u64, updates: Vec<StateUpdate>) -> Vec<SimulatedOrder> {
+    Simulator::new(snapshot(), config(interval_secs))
+        .unwrap()
+        .run(updates, None)
+        .await
+}
+
+fn kinds(timeline: &[SimulatedOrder]) -> Vec<(i64, OrderKind)> {
+    timeline
+        .iter()
+        .map(|order| ((order.at - start()).num_seconds(), order.kind))
+        .collect()
+}
+
+#[tokio::test]
+async fn faulty_sensor_escalates_one_cycle_at_a_time() {
+    let sensor = Uuid::new_v4();
+    let timeline = simulate(60, vec![update(0, &[(sensor, "Fault")])]).await;
+
+    assert_eq!(
+        kinds(&timeline),
+        [
+            (0, OrderKind::Restart),
+            (60, OrderKind::Reconfigure),
+            (120, OrderKind::Addition),
+        ]
+    );
+    assert_eq!(timeline[2].cycle, 3);
+}
+
+#[tokio::test]
+async fn recovered_sensor_stops_the_escalation() {
+    let sensor = Uuid::new_v4();
+    let updates = vec![
+        update(0, &[(sensor, "Fault")]),
+        update(90, &[(sensor, "Coherent")]),
+    ];
+
+    // A short interval escalates before the sensor comes back
+    assert_eq!(
+        kinds(&simulate(60, updates.clone()).await),
+        [(0, OrderKind::Restart), (60, OrderKind::Reconfigure)]
+    );
+    assert_eq!(
+        kinds(&simulate(120, updates).await),
+        [(0, OrderKind::Restart)]
+    );
+}
+
+#[tokio::test]
+async fn same_recording_gives_the_same_timeline() {
+    let updates = vec![update(0, &[]), update(30, &[(Uuid::new_v4(), "Fault")])];
+
+    let first = serde_json::to_value(simulate(60, updates.clone()).await).unwrap();
+    let second = serde_json::to_value(simulate(60, updates).await).unwrap();
+
+    assert!(!first.as_array().unwrap().is_empty());
+    assert_eq!(first, second);
+}
+
+#[tokio::test]
+async fn updates_of_unknown_apps_are_skipped() {
+    let mut ghost = update(0, &[(Uuid::new_v4(), "Fault")]);
+    ghost.app = "ghost".to_owned();
+
+    assert!(simulate(60, vec![ghost]).await.is_empty());
+}
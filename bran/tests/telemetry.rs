@@ -0,0 +1,77 @@
+#![cfg(feature = "otlp")]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use opentelemetry::{Key, KeyValue};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+use tracing_subscriber::layer::SubscriberExt;
+
+use bran::telemetry::otlp;
+
+use common::{addition, report, spec, MockDothing, TestBran};
+
+/// Keeps the exported spans, and the service they were exported as.
+#[derive(Debug, Clone, Default)]
+struct Collector {
+    spans: Arc<Mutex<Vec<SpanData>>>,
+    service: Arc<Mutex<Option<String>>>,
+}
+
+impl SpanExporter for Collector {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        self.spans.lock().unwrap().extend(batch);
+        Ok(())
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        *self.service.lock().unwrap() = resource
+            .get(&Key::new("service.name"))
+            .map(|v| v.to_string());
+    }
+}
+
+fn has(attributes: &[KeyValue], key: &str, value: &str) -> bool {
+    attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == key && kv.value.as_str() == value)
+}
+
+#[tokio::test]
+async fn planner_spans_and_their_fields_are_exported() {
+    let collector = Collector::default();
+    let (layer, provider) = otlp::exporting_to(collector.clone());
+    let _subscriber = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+
+    bran.register(&spec("farm", 1)).await;
+    bran.direct("addition", "farm", "l1", &addition()).await;
+    bran.report(&report("farm", 1, &[])).await;
+    assert_eq!(bran.step().await, 1);
+
+    provider.force_flush().unwrap();
+
+    assert_eq!(collector.service.lock().unwrap().as_deref(), Some("bran"));
+
+    let spans = collector.spans.lock().unwrap();
+    assert!(spans.iter().any(|s| s.name == "planner_cycle"));
+
+    let order = spans.iter().find(|s| s.name == "order").unwrap();
+    assert!(has(&order.attributes, "app", "farm"));
+    assert!(has(&order.attributes, "order_kind", "Addition"));
+
+    // Log lines keep their fields as attributes of the span events
+    let planning = spans
+        .iter()
+        .flat_map(|s| &s.events.events)
+        .find(|e| e.name == "Planning 1 Addition orders")
+        .unwrap();
+    assert!(has(&planning.attributes, "app", "farm"));
+    assert!(has(&planning.attributes, "location", "l1"));
+    assert!(has(&planning.attributes, "order_kind", "Addition"));
+}
@@ -0,0 +1,258 @@
+use std::io;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyPair,
+};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::{ClientConfig, RootCertStore};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use uuid::Uuid;
+
+/// How long bran is given to start listening or reload its certificates.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A certificate authority signing server and client certificates.
+struct Ca {
+    cert: Certificate,
+    key: KeyPair,
+}
+
+impl Ca {
+    fn new() -> Self {
+        let mut params = CertificateParams::new(vec![]).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "bran test CA");
+
+        let key = KeyPair::generate().unwrap();
+        let cert = params.self_signed(&key).unwrap();
+
+        Self { cert, key }
+    }
+
+    fn sign(&self, name: &str, usage: ExtendedKeyUsagePurpose) -> (Certificate, KeyPair) {
+        let mut params = CertificateParams::new(vec![name.to_owned()]).unwrap();
+        params.distinguished_name.push(DnType::CommonName, name);
+        params.extended_key_usages = vec![usage];
+
+        let key = KeyPair::generate().unwrap();
+        let cert = params.signed_by(&key, &self.cert, &self.key).unwrap();
+
+        (cert, key)
+    }
+
+    fn roots(&self) -> RootCertStore {
+        let mut roots = RootCertStore::empty();
+        roots.add(self.cert.der().clone()).unwrap();
+
+        roots
+    }
+}
+
+/// A bran binary serving TLS with certificates of its own, killed when
+/// dropped.
+struct Bran {
+    dir: PathBuf,
+    port: u16,
+    child: Child,
+}
+
+impl Bran {
+    fn start(ca: &Ca, require_client_cert: bool) -> Self {
+        let dir = std::env::temp_dir().join(format!("bran-tls-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        std::fs::write(dir.join("ca.pem"), ca.cert.pem()).unwrap();
+        let client_ca = if require_client_cert {
+            format!("client_ca = \"{}/ca.pem\"", dir.display())
+        } else {
+            String::new()
+        };
+
+        let config = dir.join("bran.toml");
+        std::fs::write(
+            &config,
+            format!(
+                r#"
+[server]
+port = {port}
+
+[server.tls]
+cert = "{dir}/bran.pem"
+key = "{dir}/bran.key"
+{client_ca}
+
+[audit]
+log = "{dir}/audit.log"
+
+[register]
+state_file = "{dir}/state.json"
+"#,
+                dir = dir.display()
+            ),
+        )
+        .unwrap();
+
+        install(&dir, ca);
+
+        let child = Command::new(env!("CARGO_BIN_EXE_bran"))
+            .arg("--config")
+            .arg(&config)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        Self { dir, port, child }
+    }
+
+    fn hang_up(&self) {
+        let status = Command::new("kill")
+            .args(["-HUP", &self.child.id().to_string()])
+            .status()
+            .unwrap();
+
+        assert!(status.success());
+    }
+
+    /// Waits until bran accepts connections.
+    async fn listening(&self) {
+        let started = Instant::now();
+
+        while TcpStream::connect(("127.0.0.1", self.port)).await.is_err() {
+            assert!(started.elapsed() < TIMEOUT, "bran never listened");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+}
+
+/// Writes a new server certificate signed by `ca` to `dir`, returning it.
+fn install(dir: &Path, ca: &Ca) -> CertificateDer<'static> {
+    let (cert, key) = ca.sign("localhost", ExtendedKeyUsagePurpose::ServerAuth);
+    std::fs::write(dir.join("bran.pem"), cert.pem()).unwrap();
+    std::fs::write(dir.join("bran.key"), key.serialize_pem()).unwrap();
+
+    cert.der().clone()
+}
+
+impl Drop for Bran {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// A client trusting `ca`, presenting `identity` if given.
+fn client(ca: &Ca, identity: Option<(Certificate, KeyPair)>) -> TlsConnector {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(ca.roots());
+
+    let config = match identity {
+        Some((cert, key)) => {
+            let key = PrivateKeyDer::try_from(key.serialize_der()).unwrap();
+            builder
+                .with_client_auth_cert(vec![cert.der().clone()], key)
+                .unwrap()
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    TlsConnector::from(Arc::new(config))
+}
+
+/// Asks bran if it is alive over TLS, returning the raw response and the
+/// certificate bran presented.
+async fn get_healthz(
+    bran: &Bran,
+    connector: &TlsConnector,
+) -> io::Result<(String, CertificateDer<'static>)> {
+    let tcp = TcpStream::connect(("127.0.0.1", bran.port)).await?;
+    let name = ServerName::try_from("localhost").unwrap();
+    let mut stream = connector.connect(name, tcp).await?;
+
+    let presented = stream.get_ref().1.peer_certificates().unwrap()[0].clone();
+
+    stream
+        .write_all(b"GET /healthz HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+
+    Ok((response, presented))
+}
+
+#[tokio::test]
+async fn serves_over_tls() {
+    let ca = Ca::new();
+    let bran = Bran::start(&ca, false);
+    bran.listening().await;
+
+    let (response, _) = get_healthz(&bran, &client(&ca, None)).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+}
+
+#[tokio::test]
+async fn clients_without_a_certificate_are_refused_when_one_is_required() {
+    let ca = Ca::new();
+    let bran = Bran::start(&ca, true);
+    bran.listening().await;
+
+    let refused = get_healthz(&bran, &client(&ca, None)).await;
+    assert!(refused.is_err(), "{refused:?}");
+
+    // Certificates from other authorities are no better
+    let stranger = Ca::new().sign("stranger", ExtendedKeyUsagePurpose::ClientAuth);
+    let refused = get_healthz(&bran, &client(&ca, Some(stranger))).await;
+    assert!(refused.is_err(), "{refused:?}");
+
+    let identity = ca.sign("reporter", ExtendedKeyUsagePurpose::ClientAuth);
+    let (response, _) = get_healthz(&bran, &client(&ca, Some(identity)))
+        .await
+        .unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+}
+
+#[tokio::test]
+async fn hangups_reload_the_certificates() {
+    let ca = Ca::new();
+    let bran = Bran::start(&ca, false);
+    bran.listening().await;
+
+    let connector = client(&ca, None);
+    let (_, before) = get_healthz(&bran, &connector).await.unwrap();
+
+    let renewed = install(&bran.dir, &ca);
+    assert_ne!(renewed, before);
+    bran.hang_up();
+
+    let started = Instant::now();
+    loop {
+        let (response, presented) = get_healthz(&bran, &connector).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        if presented == renewed {
+            break;
+        }
+        assert!(started.elapsed() < TIMEOUT, "bran kept the old certificate");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
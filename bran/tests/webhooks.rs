@@ -0,0 +1,159 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{body::Bytes, extract::State, http::HeaderMap, http::StatusCode, routing::post, Router};
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use starduck::Status;
+use tokio::net::TcpListener;
+use url::Url;
+
+use bran::aggregator::{ApplicationRegister, Webhook};
+use bran::config::{EventsConfig, RegisterConfig, WebhooksConfig};
+use bran::events::{EventBus, EventKind};
+use bran::notifier::Notifier;
+
+const SECRET: &str = "farm-secret";
+
+/// The signature header and body of every delivery, in order.
+type Deliveries = Arc<Mutex<Vec<(Option<String>, Bytes)>>>;
+
+/// A webhook receiver that fails its first `failures` deliveries.
+struct Receiver {
+    url: Url,
+    deliveries: Deliveries,
+}
+
+#[derive(Clone)]
+struct Hook {
+    deliveries: Deliveries,
+    failures: usize,
+}
+
+impl Receiver {
+    async fn start(failures: usize) -> Self {
+        let deliveries = Arc::new(Mutex::new(Vec::new()));
+        let hook = Hook {
+            deliveries: Arc::clone(&deliveries),
+            failures,
+        };
+
+        let app = Router::new().route("/hook", post(receive)).with_state(hook);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        Self {
+            url: Url::parse(&format!("http://{addr}/hook")).unwrap(),
+            deliveries,
+        }
+    }
+
+    /// Waits until `count` deliveries arrived, or a second went by.
+    async fn wait_for(&self, count: usize) -> Vec<(Option<String>, Bytes)> {
+        for _ in 0..100 {
+            if self.deliveries.lock().unwrap().len() >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        self.deliveries.lock().unwrap().clone()
+    }
+}
+
+async fn receive(State(hook): State<Hook>, headers: HeaderMap, body: Bytes) -> StatusCode {
+    let signature = headers
+        .get("X-Bran-Signature")
+        .map(|v| v.to_str().unwrap().to_owned());
+
+    let mut deliveries = hook.deliveries.lock().unwrap();
+    deliveries.push((signature, body));
+
+    if deliveries.len() <= hook.failures {
+        StatusCode::INTERNAL_SERVER_ERROR
+    } else {
+        StatusCode::OK
+    }
+}
+
+/// Runs a notifier for `farm`, whose webhook posts to `receiver`, and
+/// makes the app degrade.
+fn degrade_farm(receiver: &Receiver, retries: u32) {
+    let mut register = ApplicationRegister::new(&RegisterConfig::default());
+    register.webhooks.insert(
+        "farm".to_owned(),
+        Webhook {
+            url: receiver.url.clone(),
+            secret: Some(SECRET.to_owned()),
+        },
+    );
+
+    let events = Arc::new(EventBus::new(&EventsConfig::default()));
+    let config = WebhooksConfig {
+        retries,
+        backoff_secs: 0,
+    };
+    let notifier = Notifier::new(Arc::new(Mutex::new(register)), &config);
+
+    // Subscribes before the event goes out
+    let run = notifier.run(Arc::clone(&events));
+    tokio::spawn(async move {
+        tokio::join!(run, async {
+            tokio::task::yield_now().await;
+            events.publish(EventKind::StatusChanged {
+                app: "farm".to_owned(),
+                from: Some(Status::Coherent),
+                to: Status::Degraded,
+            });
+        })
+    });
+}
+
+fn sign(body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+    mac.update(body);
+
+    let digest = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+
+    format!("sha256={digest}")
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried_with_a_valid_signature() {
+    let receiver = Receiver::start(2).await;
+    degrade_farm(&receiver, 3);
+
+    let deliveries = receiver.wait_for(3).await;
+    assert_eq!(deliveries.len(), 3);
+
+    for (signature, body) in &deliveries {
+        assert_eq!(signature.as_deref(), Some(sign(body).as_str()));
+    }
+
+    let alert: Value = serde_json::from_slice(&deliveries[0].1).unwrap();
+    assert_eq!(alert["app"], "farm");
+    assert_eq!(alert["alert"], "app_non_coherent");
+
+    // Delivered on the third attempt, nothing more is sent
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(receiver.deliveries.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn deliveries_give_up_after_the_last_retry() {
+    let receiver = Receiver::start(usize::MAX).await;
+    degrade_farm(&receiver, 1);
+
+    assert_eq!(receiver.wait_for(2).await.len(), 2);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(receiver.deliveries.lock().unwrap().len(), 2);
+}
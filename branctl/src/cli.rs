@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use url::Url;
+use uuid::Uuid;
+
+/// Command line client for bran.
+#[derive(Debug, Parser)]
+#[command(name = "branctl", version)]
+pub struct Cli {
+    /// Where bran listens
+    #[arg(long, env = "BRAN_URL", default_value = "http://localhost:8014")]
+    pub url: String,
+
+    /// API token, sent as a bearer token
+    #[arg(long, env = "BRAN_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+
+    /// PEM bundle used to verify the bran certificate
+    #[arg(long, env = "BRAN_CA_CERT")]
+    pub ca_cert: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Registered applications, their specs and versions
+    #[command(subcommand)]
+    Apps(AppsCommand),
+    /// Orders bran sends when an application misbehaves
+    #[command(subcommand)]
+    Directives(DirectivesCommand),
+    /// Back up, restore or seed the whole register
+    #[command(subcommand)]
+    Register(RegisterCommand),
+    /// What the planner did and holds back
+    #[command(subcommand)]
+    Planner(PlannerCommand),
+    /// Print register and planner events as they happen
+    Events {
+        /// Only the events of this application
+        #[arg(long)]
+        app: Option<String>,
+    },
+    /// Print the audit log
+    Audit {
+        #[arg(long)]
+        app: Option<String>,
+        #[arg(long)]
+        location: Option<String>,
+        #[arg(long)]
+        since: Option<DateTime<Utc>>,
+        /// Only the last entries
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Summary of the register and the planner
+    Status,
+    /// Check that bran is alive and ready
+    Health,
+    /// Version and features of bran
+    Version,
+    /// Prometheus metrics
+    Metrics,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AppsCommand {
+    /// Applications and the status they last reported
+    List,
+    /// Spec of an application
+    Get { app: String },
+    /// State an application last reported
+    Status { app: String },
+    /// Register an application from a JSON or YAML spec
+    Register { app: String, file: PathBuf },
+    /// Replace the spec of an application
+    Update { app: String, file: PathBuf },
+    /// Report the observed state of an application
+    Report { app: String, file: PathBuf },
+    /// Stored versions of an application
+    Versions { app: String },
+    /// Spec and directives of one version
+    Version { app: String, version: u64 },
+    /// What changed between two versions
+    Diff { app: String, from: u64, to: u64 },
+    /// Put back the spec and directives of a version, keeping the current
+    /// target and webhook
+    Rollback { app: String, version: u64 },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum DirectiveKind {
+    Addition,
+    Reconfig,
+    Restart,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DirectivesCommand {
+    /// Directives of every location of an application
+    Get { app: String },
+    /// Set a directive from a JSON or YAML file
+    Set {
+        #[arg(value_enum)]
+        kind: DirectiveKind,
+        app: String,
+        location: String,
+        file: PathBuf,
+    },
+    /// Show the dothing target of an application, or set it
+    Target {
+        app: String,
+        url: Option<Url>,
+        /// Value sent in the `Authorization` header
+        #[arg(long, requires = "url")]
+        auth_header: Option<String>,
+        /// PEM bundle, on the bran host, used to verify dothing
+        #[arg(long, requires = "url")]
+        ca_cert: Option<PathBuf>,
+        #[arg(long, requires = "url")]
+        accept_invalid_certs: bool,
+    },
+    /// Show the webhook of an application, or set it
+    Webhook {
+        app: String,
+        url: Option<Url>,
+        /// Key used to sign the payloads
+        #[arg(long, requires = "url")]
+        secret: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RegisterCommand {
+    /// Print every application and directive as one snapshot
+    Export {
+        /// Write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Keep the dothing credentials and webhook secrets. Needs
+        /// authentication to be enabled
+        #[arg(long)]
+        secrets: bool,
+    },
+    /// Load a snapshot written by `export`
+    Import {
+        file: PathBuf,
+        /// Drop the applications missing from the snapshot
+        #[arg(long)]
+        replace: bool,
+        /// Only print what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PlannerCommand {
+    /// Last cycle, deferred orders and circuits
+    Show,
+    /// Orders planned on the last cycle, before the limits and approvals
+    Planned,
+    /// Additions ordered for an application
+    Allocations { app: String },
+    /// Let the planner order a failed addition again
+    Retry { app: String, device_uuid: Uuid },
+    /// Send the orders of an application awaiting approval
+    Approve {
+        app: String,
+        /// Only the orders for this location
+        #[arg(long)]
+        location: Option<String>,
+    },
+    /// Circuit breaker of every dothing target
+    Circuits,
+}
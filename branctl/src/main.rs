@@ -0,0 +1,277 @@
+mod cli;
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use bran_client::{AuditQuery, BranClient, DothingTarget, ImportMode, TargetTls, Webhook};
+use clap::Parser;
+use futures_util::StreamExt;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+
+use cli::{
+    AppsCommand, Cli, Command, DirectiveKind, DirectivesCommand, PlannerCommand, RegisterCommand,
+};
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    if let Err(e) = run(cli).await {
+        // Output piped into `head` and the like
+        if e.downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+        {
+            return;
+        }
+
+        eprintln!("{e:#}");
+        std::process::exit(-1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let bran = client(&cli)?;
+
+    match cli.command {
+        Command::Apps(command) => apps(&bran, command).await,
+        Command::Directives(command) => directives(&bran, command).await,
+        Command::Register(command) => register(&bran, command).await,
+        Command::Planner(command) => planner(&bran, command).await,
+        Command::Events { app } => {
+            let mut events = bran.events(app.as_deref()).await?;
+
+            while let Some(event) = events.next().await {
+                writeln!(io::stdout(), "{}", serde_json::to_string(&event?)?)?;
+            }
+
+            Ok(())
+        }
+        Command::Audit {
+            app,
+            location,
+            since,
+            limit,
+        } => {
+            let query = AuditQuery {
+                app,
+                location,
+                since,
+                limit,
+            };
+            print_json(&bran.get_audit_entries(&query).await?)
+        }
+        Command::Status => print_json(&bran.status().await?),
+        Command::Health => {
+            bran.health().await?;
+            let readiness = bran.readiness().await?;
+            print_json(&readiness)?;
+
+            if !readiness.is_ready() {
+                bail!("bran is alive but not ready");
+            }
+            Ok(())
+        }
+        Command::Version => print_json(&bran.version().await?),
+        Command::Metrics => {
+            write!(io::stdout(), "{}", bran.metrics().await?)?;
+            Ok(())
+        }
+    }
+}
+
+fn client(cli: &Cli) -> Result<BranClient> {
+    let mut http = reqwest::Client::builder();
+
+    if let Some(path) = &cli.ca_cert {
+        let pem =
+            std::fs::read(path).with_context(|| format!("Could not read {}", path.display()))?;
+        http = http.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+    }
+
+    let bran = BranClient::with_http(&cli.url, http.build()?)?;
+
+    Ok(match &cli.token {
+        Some(token) => bran.with_token(token),
+        None => bran,
+    })
+}
+
+async fn apps(bran: &BranClient, command: AppsCommand) -> Result<()> {
+    match command {
+        AppsCommand::List => print_json(&bran.list_apps().await?),
+        AppsCommand::Get { app } => print_json(&bran.get_app(&app).await?),
+        AppsCommand::Status { app } => print_json(&bran.get_app_status(&app).await?),
+        AppsCommand::Register { app, file } => {
+            bran.register_app(&app, &read_file(&file)?).await?;
+            done(format!("Registered {app}"))
+        }
+        AppsCommand::Update { app, file } => {
+            bran.update_app(&app, &read_file(&file)?).await?;
+            done(format!("Updated the spec of {app}"))
+        }
+        AppsCommand::Report { app, file } => {
+            bran.report_state(&app, &read_file(&file)?).await?;
+            done(format!("Reported the state of {app}"))
+        }
+        AppsCommand::Versions { app } => print_json(&bran.list_versions(&app).await?),
+        AppsCommand::Version { app, version } => {
+            print_json(&bran.get_version(&app, version).await?)
+        }
+        AppsCommand::Diff { app, from, to } => {
+            print_json(&bran.diff_versions(&app, from, to).await?)
+        }
+        AppsCommand::Rollback { app, version } => {
+            bran.rollback(&app, version).await?;
+            done(format!("Rolled back {app} to version {version}"))
+        }
+    }
+}
+
+async fn directives(bran: &BranClient, command: DirectivesCommand) -> Result<()> {
+    match command {
+        DirectivesCommand::Get { app } => print_json(&bran.get_directives(&app).await?),
+        DirectivesCommand::Set {
+            kind,
+            app,
+            location,
+            file,
+        } => {
+            match kind {
+                DirectiveKind::Addition => {
+                    bran.set_addition(&app, &location, &read_file(&file)?)
+                        .await?
+                }
+                DirectiveKind::Reconfig => {
+                    bran.set_reconfig(&app, &location, &read_file(&file)?)
+                        .await?
+                }
+                DirectiveKind::Restart => {
+                    bran.set_restart(&app, &location, &read_file(&file)?)
+                        .await?
+                }
+            }
+            done(format!("Set the {kind:?} directive of {location} in {app}"))
+        }
+        DirectivesCommand::Target { app, url: None, .. } => {
+            print_json(&bran.get_target(&app).await?)
+        }
+        DirectivesCommand::Target {
+            app,
+            url: Some(url),
+            auth_header,
+            ca_cert,
+            accept_invalid_certs,
+        } => {
+            let target = DothingTarget {
+                url,
+                auth_header,
+                tls: TargetTls {
+                    ca_cert,
+                    accept_invalid_certs,
+                },
+            };
+            bran.set_target(&app, &target).await?;
+            done(format!("Set the dothing target of {app}"))
+        }
+        DirectivesCommand::Webhook { app, url: None, .. } => {
+            print_json(&bran.get_webhook(&app).await?)
+        }
+        DirectivesCommand::Webhook {
+            app,
+            url: Some(url),
+            secret,
+        } => {
+            bran.set_webhook(&app, &Webhook { url, secret }).await?;
+            done(format!("Set the webhook of {app}"))
+        }
+    }
+}
+
+async fn register(bran: &BranClient, command: RegisterCommand) -> Result<()> {
+    match command {
+        RegisterCommand::Export {
+            output: None,
+            secrets,
+        } => print_json(&bran.export_register(secrets).await?),
+        RegisterCommand::Export {
+            output: Some(path),
+            secrets,
+        } => {
+            let snapshot = bran.export_register(secrets).await?;
+            std::fs::write(&path, serde_json::to_string_pretty(&snapshot)?)
+                .with_context(|| format!("Could not write {}", path.display()))?;
+            done(format!(
+                "Exported {} applications to {}",
+                snapshot.apps.len(),
+                path.display()
+            ))
+        }
+        RegisterCommand::Import {
+            file,
+            replace,
+            dry_run,
+        } => {
+            let mode = if replace {
+                ImportMode::Replace
+            } else {
+                ImportMode::Merge
+            };
+            let report = bran
+                .import_register(&read_file(&file)?, mode, dry_run)
+                .await?;
+
+            print_json(&report.changes)?;
+            done(report.msg)
+        }
+    }
+}
+
+async fn planner(bran: &BranClient, command: PlannerCommand) -> Result<()> {
+    match command {
+        PlannerCommand::Show => {
+            let status = bran.status().await?;
+            let deferred = bran.get_deferred_orders().await?;
+
+            print_json(&json!({
+                "planner": status.planner,
+                "deferred": deferred,
+                "circuits": status.circuits,
+            }))
+        }
+        PlannerCommand::Planned => print_json(&bran.get_planned_orders().await?),
+        PlannerCommand::Allocations { app } => print_json(&bran.get_allocations(&app).await?),
+        PlannerCommand::Retry { app, device_uuid } => {
+            bran.clear_failed_allocation(&app, device_uuid).await?;
+            done(format!("Cleared failed addition {device_uuid} of {app}"))
+        }
+        PlannerCommand::Approve { app, location } => {
+            done(bran.approve_orders(&app, location.as_deref()).await?.msg)
+        }
+        PlannerCommand::Circuits => print_json(&bran.get_circuits().await?),
+    }
+}
+
+/// JSON, or YAML for files ending in .yaml or .yml.
+fn read_file<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+
+    let parsed = match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml" | "yml") => serde_yaml::from_str(&content)?,
+        _ => serde_json::from_str(&content)?,
+    };
+
+    Ok(parsed)
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    writeln!(io::stdout(), "{}", serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+fn done(msg: String) -> Result<()> {
+    eprintln!("{msg}");
+    Ok(())
+}
@@ -0,0 +1,136 @@
+// The bran test harness, so branctl talks to a real bran
+#[path = "../../bran/tests/common/mod.rs"]
+mod common;
+
+use std::path::PathBuf;
+use std::process::Output;
+
+use serde_json::{json, Value};
+use tokio::process::Command;
+use uuid::Uuid;
+
+use common::{addition, report, spec, MockDothing, TestBran};
+
+/// Runs branctl against `bran`, ignoring the settings of whoever runs the
+/// tests.
+async fn branctl(bran: &TestBran, args: &[&str]) -> Output {
+    branctl_at(&bran.url, args).await
+}
+
+async fn branctl_at(url: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_branctl"))
+        .arg("--url")
+        .arg(url)
+        .args(args)
+        .env_remove("BRAN_TOKEN")
+        .env_remove("BRAN_CA_CERT")
+        .output()
+        .await
+        .unwrap()
+}
+
+/// The JSON branctl printed, panicking if it failed.
+fn printed(output: &Output) -> Value {
+    assert!(output.status.success(), "{}", stderr(output));
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// A file of its own, removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(extension: &str, content: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("branctl-{}.{extension}", Uuid::new_v4()));
+        std::fs::write(&path, content).unwrap();
+
+        Self(path)
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[tokio::test]
+async fn apps_and_directives_go_through_bran() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    let file = TempFile::new("json", &spec("farm", 1).to_string());
+    let output = branctl(&bran, &["apps", "register", "farm", file.path()]).await;
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stderr(&output).trim(), "Registered farm");
+
+    let apps = printed(&branctl(&bran, &["apps", "list"]).await);
+    assert_eq!(apps, json!([{"name": "farm", "status": null}]));
+
+    // Directives are read from YAML too
+    let file = TempFile::new("yaml", &serde_yaml::to_string(&addition()).unwrap());
+    let output = branctl(
+        &bran,
+        &["directives", "set", "addition", "farm", "l1", file.path()],
+    )
+    .await;
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let directives = printed(&branctl(&bran, &["directives", "get", "farm"]).await);
+    assert_eq!(directives["l1"]["addition"], addition());
+}
+
+#[tokio::test]
+async fn planned_orders_are_those_of_the_last_cycle() {
+    let dothing = MockDothing::start().await;
+    let mut bran = TestBran::start(&dothing).await;
+
+    assert_eq!(
+        printed(&branctl(&bran, &["planner", "planned"]).await),
+        json!([])
+    );
+
+    bran.register(&spec("farm", 1)).await;
+    bran.direct("addition", "farm", "l1", &addition()).await;
+    bran.report(&report("farm", 1, &[])).await;
+    bran.step().await;
+
+    let planned = printed(&branctl(&bran, &["planner", "planned"]).await);
+    let planned = planned.as_array().unwrap();
+    assert_eq!(planned.len(), 1);
+    assert_eq!(planned[0]["app_name"], "farm");
+    assert_eq!(planned[0]["kind"], "Addition");
+    assert_eq!(planned[0]["problem"]["location_key"], "l1");
+    assert_eq!(planned[0]["target"], dothing.url.to_string());
+    assert_eq!(planned[0]["order"], addition());
+}
+
+#[tokio::test]
+async fn failures_exit_with_what_went_wrong() {
+    let dothing = MockDothing::start().await;
+    let bran = TestBran::start(&dothing).await;
+
+    let output = branctl(&bran, &["apps", "get", "ghost"]).await;
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).starts_with("not found: "),
+        "{}",
+        stderr(&output)
+    );
+    assert!(output.stdout.is_empty());
+
+    let output = branctl_at("ftp://localhost:8014", &["apps", "list"]).await;
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("must be http or https"),
+        "{}",
+        stderr(&output)
+    );
+}